@@ -0,0 +1,661 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::mem;
+
+use crate::map::Map;
+use crate::number::JsonNumber;
+use crate::JsonValue;
+
+/// Why a `JsonValue` couldn't be converted into a Rust type with `TryFrom`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    pub kind: ConversionErrorKind,
+    /// A JSON Pointer to the value that didn't fit, e.g. `/users/0/age`,
+    /// which `JsonValue::pointer` will find. Empty for the value itself.
+    pub pointer: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionErrorKind {
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// A number that isn't an integer, or is outside the target's range.
+    OutOfRange {
+        number: String,
+        target: &'static str,
+    },
+    /// An array converted into a tuple of a different length.
+    WrongLength { expected: usize, found: usize },
+}
+
+impl ConversionError {
+    fn new(kind: ConversionErrorKind) -> Self {
+        ConversionError {
+            kind,
+            pointer: String::new(),
+        }
+    }
+
+    fn wrong_type(expected: &'static str, found: &JsonValue<'_>) -> Self {
+        ConversionError::new(ConversionErrorKind::WrongType {
+            expected,
+            found: type_name(found),
+        })
+    }
+
+    /// Moves the error down into the element or member at `step`.
+    fn within(mut self, step: &str) -> Self {
+        let step = step.replace('~', "~0").replace('/', "~1");
+        self.pointer.insert_str(0, &format!("/{}", step));
+        self
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ConversionErrorKind::WrongType { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)?
+            }
+            ConversionErrorKind::OutOfRange { number, target } => {
+                write!(f, "number {} does not fit in {}", number, target)?
+            }
+            ConversionErrorKind::WrongLength { expected, found } => write!(
+                f,
+                "expected an array of {} elements, found {}",
+                expected, found
+            )?,
+        }
+        if !self.pointer.is_empty() {
+            write!(f, " at {}", self.pointer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConversionError {}
+
+fn type_name(value: &JsonValue<'_>) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "a boolean",
+        JsonValue::Number(_) => "a number",
+        JsonValue::String(_) => "a string",
+        JsonValue::Array(_) => "an array",
+        JsonValue::Object(_) => "an object",
+    }
+}
+
+impl From<()> for JsonValue<'_> {
+    fn from(_: ()) -> Self {
+        JsonValue::Null
+    }
+}
+
+impl From<bool> for JsonValue<'_> {
+    fn from(b: bool) -> Self {
+        JsonValue::Bool(b)
+    }
+}
+
+impl From<JsonNumber> for JsonValue<'_> {
+    fn from(n: JsonNumber) -> Self {
+        JsonValue::Number(n)
+    }
+}
+
+macro_rules! from_integer {
+    ($via:ty: $($t:ty)*) => {
+        $(
+            impl From<$t> for JsonValue<'_> {
+                fn from(n: $t) -> Self {
+                    JsonValue::Number(JsonNumber::from(n as $via))
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i64: i8 i16 i32 i64 isize);
+from_integer!(u64: u8 u16 u32 u64 usize);
+
+impl From<f32> for JsonValue<'_> {
+    fn from(f: f32) -> Self {
+        JsonValue::Number(JsonNumber::from(f as f64))
+    }
+}
+
+impl From<f64> for JsonValue<'_> {
+    fn from(f: f64) -> Self {
+        JsonValue::Number(JsonNumber::from(f))
+    }
+}
+
+impl<'a> From<&'a str> for JsonValue<'a> {
+    fn from(s: &'a str) -> Self {
+        JsonValue::String(Cow::Borrowed(s))
+    }
+}
+
+impl From<String> for JsonValue<'_> {
+    fn from(s: String) -> Self {
+        JsonValue::String(Cow::Owned(s))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for JsonValue<'a> {
+    fn from(s: Cow<'a, str>) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl From<char> for JsonValue<'_> {
+    fn from(c: char) -> Self {
+        JsonValue::String(Cow::Owned(c.to_string()))
+    }
+}
+
+impl<'a> From<Map<'a>> for JsonValue<'a> {
+    fn from(map: Map<'a>) -> Self {
+        JsonValue::Object(map)
+    }
+}
+
+/// `None` becomes `Null`.
+impl<'a, T: Into<JsonValue<'a>>> From<Option<T>> for JsonValue<'a> {
+    fn from(option: Option<T>) -> Self {
+        option.map_or(JsonValue::Null, Into::into)
+    }
+}
+
+impl<'a, T: Into<JsonValue<'a>>> From<Vec<T>> for JsonValue<'a> {
+    fn from(items: Vec<T>) -> Self {
+        JsonValue::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<'a, T: Into<JsonValue<'a>>, const N: usize> From<[T; N]> for JsonValue<'a> {
+    fn from(items: [T; N]) -> Self {
+        JsonValue::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<'a, T: Clone + Into<JsonValue<'a>>> From<&[T]> for JsonValue<'a> {
+    fn from(items: &[T]) -> Self {
+        JsonValue::Array(items.iter().cloned().map(Into::into).collect())
+    }
+}
+
+/// The members end up in the map's iteration order, so sorted by key.
+impl<'a, K, V> From<BTreeMap<K, V>> for JsonValue<'a>
+where
+    K: Into<Cow<'a, str>>,
+    V: Into<JsonValue<'a>>,
+{
+    fn from(map: BTreeMap<K, V>) -> Self {
+        JsonValue::Object(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+/// The members end up in the map's iteration order, which is arbitrary.
+impl<'a, K, V, S> From<HashMap<K, V, S>> for JsonValue<'a>
+where
+    K: Into<Cow<'a, str>>,
+    V: Into<JsonValue<'a>>,
+{
+    fn from(map: HashMap<K, V, S>) -> Self {
+        JsonValue::Object(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+impl<'a> TryFrom<JsonValue<'a>> for () {
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        match value {
+            JsonValue::Null => Ok(()),
+            _ => Err(ConversionError::wrong_type("null", &value)),
+        }
+    }
+}
+
+impl<'a> TryFrom<JsonValue<'a>> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        value
+            .as_bool()
+            .ok_or_else(|| ConversionError::wrong_type("a boolean", &value))
+    }
+}
+
+impl<'a> TryFrom<JsonValue<'a>> for JsonNumber {
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        value
+            .as_number()
+            .cloned()
+            .ok_or_else(|| ConversionError::wrong_type("a number", &value))
+    }
+}
+
+macro_rules! try_from_integer {
+    ($as:ident: $($t:ident)*) => {
+        $(
+            impl<'a> TryFrom<JsonValue<'a>> for $t {
+                type Error = ConversionError;
+
+                fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+                    let n = value
+                        .as_number()
+                        .ok_or_else(|| ConversionError::wrong_type("a number", &value))?;
+                    n.$as()
+                        .and_then(|n| $t::try_from(n).ok())
+                        .ok_or_else(|| {
+                            ConversionError::new(ConversionErrorKind::OutOfRange {
+                                number: n.to_string(),
+                                target: stringify!($t),
+                            })
+                        })
+                }
+            }
+        )*
+    };
+}
+
+try_from_integer!(as_i64: i8 i16 i32 i64 isize);
+try_from_integer!(as_u64: u8 u16 u32 u64 usize);
+
+/// Any number, rounded to the nearest `f64`.
+impl<'a> TryFrom<JsonValue<'a>> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        value
+            .as_f64()
+            .ok_or_else(|| ConversionError::wrong_type("a number", &value))
+    }
+}
+
+impl<'a> TryFrom<JsonValue<'a>> for f32 {
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        f64::try_from(value).map(|f| f as f32)
+    }
+}
+
+impl<'a> TryFrom<JsonValue<'a>> for Cow<'a, str> {
+    type Error = ConversionError;
+
+    fn try_from(mut value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        match &mut value {
+            JsonValue::String(s) => Ok(mem::take(s)),
+            _ => Err(ConversionError::wrong_type("a string", &value)),
+        }
+    }
+}
+
+impl<'a> TryFrom<JsonValue<'a>> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        Cow::try_from(value).map(Cow::into_owned)
+    }
+}
+
+impl<'a> TryFrom<JsonValue<'a>> for Map<'a> {
+    type Error = ConversionError;
+
+    fn try_from(mut value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        match &mut value {
+            JsonValue::Object(map) => Ok(mem::take(map)),
+            _ => Err(ConversionError::wrong_type("an object", &value)),
+        }
+    }
+}
+
+/// `Null` becomes `None`.
+impl<'a, T> TryFrom<JsonValue<'a>> for Option<T>
+where
+    T: TryFrom<JsonValue<'a>, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        match value {
+            JsonValue::Null => Ok(None),
+            _ => T::try_from(value).map(Some),
+        }
+    }
+}
+
+fn elements<'a>(mut value: JsonValue<'a>) -> Result<Vec<JsonValue<'a>>, ConversionError> {
+    match &mut value {
+        JsonValue::Array(items) => Ok(mem::take(items)),
+        _ => Err(ConversionError::wrong_type("an array", &value)),
+    }
+}
+
+impl<'a, T> TryFrom<JsonValue<'a>> for Vec<T>
+where
+    T: TryFrom<JsonValue<'a>, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        elements(value)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| T::try_from(item).map_err(|e| e.within(&i.to_string())))
+            .collect()
+    }
+}
+
+/// The members of an object, with the key of the first one that doesn't
+/// convert in the error. Of repeated keys, the last one wins.
+fn members<'a, V, C>(value: JsonValue<'a>) -> Result<C, ConversionError>
+where
+    V: TryFrom<JsonValue<'a>, Error = ConversionError>,
+    C: FromIterator<(String, V)>,
+{
+    Map::try_from(value)?
+        .into_iter()
+        .map(|(key, value)| match V::try_from(value) {
+            Ok(value) => Ok((key.into_owned(), value)),
+            Err(e) => Err(e.within(&key)),
+        })
+        .collect()
+}
+
+impl<'a, V> TryFrom<JsonValue<'a>> for BTreeMap<String, V>
+where
+    V: TryFrom<JsonValue<'a>, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        members(value)
+    }
+}
+
+impl<'a, V, S> TryFrom<JsonValue<'a>> for HashMap<String, V, S>
+where
+    V: TryFrom<JsonValue<'a>, Error = ConversionError>,
+    S: BuildHasher + Default,
+{
+    type Error = ConversionError;
+
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+        members(value)
+    }
+}
+
+/// Tuples convert to and from arrays of the same length, element by element.
+macro_rules! tuple {
+    ($len:literal: $($t:ident $i:tt)+) => {
+        impl<'a, $($t: Into<JsonValue<'a>>),+> From<($($t,)+)> for JsonValue<'a> {
+            fn from(tuple: ($($t,)+)) -> Self {
+                JsonValue::Array(vec![$(tuple.$i.into()),+])
+            }
+        }
+
+        impl<'a, $($t),+> TryFrom<JsonValue<'a>> for ($($t,)+)
+        where
+            $($t: TryFrom<JsonValue<'a>, Error = ConversionError>),+
+        {
+            type Error = ConversionError;
+
+            fn try_from(value: JsonValue<'a>) -> Result<Self, ConversionError> {
+                let items = elements(value)?;
+                if items.len() != $len {
+                    return Err(ConversionError::new(ConversionErrorKind::WrongLength {
+                        expected: $len,
+                        found: items.len(),
+                    }));
+                }
+                let mut items = items.into_iter();
+                Ok(($(
+                    $t::try_from(items.next().expect("length checked above"))
+                        .map_err(|e| e.within(stringify!($i)))?,
+                )+))
+            }
+        }
+    };
+}
+
+tuple!(1: A 0);
+tuple!(2: A 0 B 1);
+tuple!(3: A 0 B 1 C 2);
+tuple!(4: A 0 B 1 C 2 D 3);
+tuple!(5: A 0 B 1 C 2 D 3 E 4);
+tuple!(6: A 0 B 1 C 2 D 3 E 4 F 5);
+tuple!(7: A 0 B 1 C 2 D 3 E 4 F 5 G 6);
+tuple!(8: A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7);
+
+/// Builds a `JsonValue` from JSON-like syntax:
+///
+/// ```
+/// use json_parse::json;
+///
+/// let name = "Alice";
+/// let pets = vec!["Cat", "Dog"];
+/// let value = json!({
+///     "name": name,
+///     "age": 30,
+///     "children": null,
+///     "pets": pets,
+///     "address": { "city": "Wonderland", "zip": format!("{:05}", 12345) },
+/// });
+/// println!("{:#}", value);
+/// ```
+///
+/// Anything other than `null`, `true`, `false`, `[...]` and `{...}` is a Rust
+/// expression, converted with `JsonValue::from`. Keys are any expression
+/// that converts into `Cow<str>`; put one in parentheses if it contains a
+/// ':'. Members keep the order they are written in, and trailing commas are
+/// allowed.
+#[macro_export]
+macro_rules! json {
+    // Arrays: `@array [built elements] rest`.
+    (@array [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+    (@array [$($elems:expr),*]) => {
+        vec![$($elems),*]
+    };
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!(null)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] true $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!(true)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] false $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!(false)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!([$($array)*])] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!({$($object)*})] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!($next),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::json!(@array [$($elems,)* $crate::json!($last)])
+    };
+    (@array [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)*] $($rest)*)
+    };
+
+    // Objects: `@object map (key tokens so far) (rest)`, and
+    // `@object map [key] (value) rest` once a member is complete.
+    (@object $map:ident () ()) => {};
+    (@object $map:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        $map.insert(($($key)+), $value);
+        $crate::json!(@object $map () ($($rest)*));
+    };
+    (@object $map:ident [$($key:tt)+] ($value:expr)) => {
+        $map.insert(($($key)+), $value);
+    };
+    (@object $map:ident ($($key:tt)+) (: null $($rest:tt)*)) => {
+        $crate::json!(@object $map [$($key)+] ($crate::json!(null)) $($rest)*);
+    };
+    (@object $map:ident ($($key:tt)+) (: true $($rest:tt)*)) => {
+        $crate::json!(@object $map [$($key)+] ($crate::json!(true)) $($rest)*);
+    };
+    (@object $map:ident ($($key:tt)+) (: false $($rest:tt)*)) => {
+        $crate::json!(@object $map [$($key)+] ($crate::json!(false)) $($rest)*);
+    };
+    (@object $map:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*)) => {
+        $crate::json!(@object $map [$($key)+] ($crate::json!([$($array)*])) $($rest)*);
+    };
+    (@object $map:ident ($($key:tt)+) (: {$($object:tt)*} $($rest:tt)*)) => {
+        $crate::json!(@object $map [$($key)+] ($crate::json!({$($object)*})) $($rest)*);
+    };
+    (@object $map:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*)) => {
+        $crate::json!(@object $map [$($key)+] ($crate::json!($value)) , $($rest)*);
+    };
+    (@object $map:ident ($($key:tt)+) (: $value:expr)) => {
+        $crate::json!(@object $map [$($key)+] ($crate::json!($value)));
+    };
+    (@object $map:ident ($($key:tt)*) ($tt:tt $($rest:tt)*)) => {
+        $crate::json!(@object $map ($($key)* $tt) ($($rest)*));
+    };
+
+    (null) => {
+        $crate::JsonValue::Null
+    };
+    (true) => {
+        $crate::JsonValue::Bool(true)
+    };
+    (false) => {
+        $crate::JsonValue::Bool(false)
+    };
+    ([]) => {
+        $crate::JsonValue::Array(vec![])
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::JsonValue::Array($crate::json!(@array [] $($tt)+))
+    };
+    ({}) => {
+        $crate::JsonValue::Object($crate::map::Map::new())
+    };
+    ({ $($tt:tt)+ }) => {
+        $crate::JsonValue::Object({
+            let mut map = $crate::map::Map::new();
+            $crate::json!(@object map () ($($tt)+));
+            map
+        })
+    };
+    ($other:expr) => {
+        $crate::JsonValue::from($other)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error<T: TryFrom<JsonValue<'static>, Error = ConversionError> + fmt::Debug>(
+        value: JsonValue<'static>,
+    ) -> ConversionError {
+        T::try_from(value).unwrap_err()
+    }
+
+    #[test]
+    fn errors_point_at_the_value() {
+        type Users = BTreeMap<String, Vec<HashMap<String, u32>>>;
+        let value = json!({"users": [{"age": "30"}]});
+        let e = error::<Users>(value);
+        assert_eq!(e.pointer, "/users/0/age");
+        assert_eq!(
+            e.to_string(),
+            "expected a number, found a string at /users/0/age"
+        );
+
+        // '/' and '~' in keys are escaped as in JSON Pointer
+        let value = json!({"a/b": {"~": null}});
+        let e = error::<HashMap<String, HashMap<String, bool>>>(value.clone());
+        assert_eq!(e.pointer, "/a~1b/~0");
+        assert!(value.pointer(&e.pointer).unwrap().is_null());
+
+        let e = error::<(u8, (bool, String))>(json!([1, [true, 2]]));
+        assert_eq!(e.to_string(), "expected a string, found a number at /1/1");
+        assert_eq!(error::<String>(json!(null)).pointer, "");
+    }
+
+    #[test]
+    fn numbers_out_of_range() {
+        let e = error::<u8>(json!(300));
+        assert_eq!(
+            e.kind,
+            ConversionErrorKind::OutOfRange {
+                number: "300".to_string(),
+                target: "u8",
+            }
+        );
+        assert_eq!(e.to_string(), "number 300 does not fit in u8");
+        assert!(matches!(
+            error::<i64>(json!(1.0)).kind,
+            ConversionErrorKind::OutOfRange { target: "i64", .. }
+        ));
+        assert!(matches!(
+            error::<Vec<u32>>(json!([1, -1])),
+            ConversionError { kind: ConversionErrorKind::OutOfRange { .. }, ref pointer }
+                if pointer == "/1"
+        ));
+        assert_eq!(u8::try_from(json!(255)), Ok(255));
+        assert_eq!(i8::try_from(json!(-128)), Ok(-128));
+        assert_eq!(f64::try_from(json!(3)), Ok(3.0));
+    }
+
+    #[test]
+    fn tuples_need_the_right_length() {
+        let e = error::<(u8, u8)>(json!([1, 2, 3]));
+        assert_eq!(
+            e.kind,
+            ConversionErrorKind::WrongLength {
+                expected: 2,
+                found: 3,
+            }
+        );
+        assert_eq!(e.to_string(), "expected an array of 2 elements, found 3");
+        assert_eq!(
+            error::<(bool,)>(json!([])).to_string(),
+            "expected an array of 1 elements, found 0"
+        );
+        assert_eq!(
+            <(u8, String)>::try_from(json!([1, "a"])),
+            Ok((1, "a".to_string()))
+        );
+        assert_eq!(
+            JsonValue::from((1, "a", None::<bool>)),
+            json!([1, "a", null])
+        );
+    }
+
+    #[test]
+    fn json_macro() {
+        let key = "k";
+        let value = json!({
+            key: [1, 2,],
+            (format!("{}:{}", "a", "b")): {"nested": true,},
+            "n": null,
+        });
+        let keys: Vec<&str> = value.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["k", "a:b", "n"]);
+        assert_eq!(value["k"], JsonValue::from(vec![1, 2]));
+        assert_eq!(value["a:b"]["nested"], JsonValue::Bool(true));
+        assert_eq!(json!([null, [], {},]).to_string(), "[null,[],{}]");
+        assert_eq!(json!(1 + 2), JsonValue::from(3));
+        assert_eq!(Option::<u8>::try_from(json!(null)), Ok(None));
+    }
+}
@@ -0,0 +1,830 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::map::Map;
+use crate::ser;
+use crate::{
+    owned, Dialect, Expected, JsonError, JsonValue, LimitKind, Parser, ParserOptions, Token,
+};
+
+/// One step of a path into a `Document`: an object key or an array index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment<'p> {
+    Key(&'p str),
+    Index(usize),
+}
+
+impl<'p> From<&'p str> for PathSegment<'p> {
+    fn from(key: &'p str) -> Self {
+        PathSegment::Key(key)
+    }
+}
+
+impl From<usize> for PathSegment<'_> {
+    fn from(index: usize) -> Self {
+        PathSegment::Index(index)
+    }
+}
+
+/// Why a `Document` edit could not be made. The document is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditError {
+    NotFound,  // nothing at the path, or an index past the end
+    WrongType, // a key into something other than an object, or an index into a non-array
+    EmptyPath, // `remove` of the root
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::NotFound => write!(f, "no value at this path"),
+            EditError::WrongType => write!(f, "path does not match the document's structure"),
+            EditError::EmptyPath => write!(f, "the root value cannot be removed"),
+        }
+    }
+}
+
+impl Error for EditError {}
+
+/// A JSON document as a concrete syntax tree, which keeps every byte of the
+/// input: whitespace, comments (with `Dialect::Jsonc` or `Dialect::Json5`)
+/// and the original spelling of every string and number. `to_string` gives
+/// back the input exactly, so a tool can change one value through `get_mut`
+/// and write the file back with everything else untouched.
+///
+/// `set`, `insert_key`, `insert` and `remove` edit the tree in place. New
+/// values are written compactly, and new elements and members copy the
+/// indentation and separator spacing of their siblings.
+///
+/// Comments are attached to the nearest array element or object member:
+/// those on the lines before it, and one on the same line after it (after
+/// its comma, if any).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    leading: String, // whitespace and comments before the root value
+    root: Node,
+    trailing: String, // and after it
+}
+
+/// A value in a `Document`, with the exact text it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Scalar {
+        raw: String, // the token as written, e.g. `1.50` or `"é"`
+        value: JsonValue<'static>,
+    },
+    Array(Container),
+    Object(Container),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Container {
+    items: Vec<Item>,
+    close_leading: String, // trivia before the closing bracket
+}
+
+/// An array element or object member, with the trivia around it. Written
+/// out as `leading key value before_comma ',' trailing`.
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    leading: String,
+    key: Option<Key>, // `None` in arrays
+    value: Node,
+    before_comma: String,
+    comma: bool,
+    trailing: String, // a comment on the same line after the item
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Key {
+    name: String, // with escapes decoded, for lookups
+    raw: String,  // as written, quotes included
+    before_colon: String,
+    after_colon: String,
+}
+
+impl Document {
+    pub fn parse(input: &str) -> Result<Document, JsonError> {
+        Document::parse_with_options(input, ParserOptions::default())
+    }
+
+    /// Parses in `options.dialect`. Objects repeating a key keep every
+    /// member, and lookups find the last one.
+    pub fn parse_with_options(input: &str, options: ParserOptions) -> Result<Document, JsonError> {
+        let mut builder = Builder {
+            input,
+            parser: Parser::with_options(input, options),
+        };
+        builder.parser.check_input_size()?;
+        let leading = builder.trivia()?;
+        let root = builder.node()?;
+        let trailing = builder.trivia()?;
+        if builder.parser.current_token()?.is_some() {
+            return Err(builder.parser.unexpected(Expected::Eof));
+        }
+        Ok(Document {
+            leading,
+            root,
+            trailing,
+        })
+    }
+
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// The node at `path`, starting from the root.
+    pub fn get(&self, path: &[PathSegment<'_>]) -> Option<&Node> {
+        path.iter()
+            .try_fold(&self.root, |node, &segment| node.get(segment))
+    }
+
+    pub fn get_mut(&mut self, path: &[PathSegment<'_>]) -> Option<&mut Node> {
+        path.iter()
+            .try_fold(&mut self.root, |node, &segment| node.get_mut(segment))
+    }
+
+    /// The comments attached to the node at `path`, in source order. For the
+    /// root these are the comments before and after it.
+    pub fn comments(&self, path: &[PathSegment<'_>]) -> Option<Vec<&str>> {
+        let (&last, parent) = match path.split_last() {
+            Some(split) => split,
+            None => {
+                let trivia = [self.leading.as_str(), &self.trailing];
+                return Some(trivia.into_iter().flat_map(comments_in).collect());
+            }
+        };
+        let item = self.get(parent)?.item(last)?;
+        let mut trivia = vec![item.leading.as_str()];
+        if let Some(key) = &item.key {
+            trivia.push(&key.before_colon);
+            trivia.push(&key.after_colon);
+        }
+        trivia.push(&item.before_comma);
+        trivia.push(&item.trailing);
+        Some(trivia.into_iter().flat_map(comments_in).collect())
+    }
+
+    /// Replaces the value at `path`. If the last step is a key the object
+    /// doesn't have yet, the member is added as by `insert_key`.
+    pub fn set(
+        &mut self,
+        path: &[PathSegment<'_>],
+        value: &JsonValue<'_>,
+    ) -> Result<(), EditError> {
+        let (&last, parent) = match path.split_last() {
+            Some(split) => split,
+            None => {
+                self.root.replace(value);
+                return Ok(());
+            }
+        };
+        let parent = self.get_mut(parent).ok_or(EditError::NotFound)?;
+        match (parent.get_mut(last), last) {
+            (Some(node), _) => node.replace(value),
+            (None, PathSegment::Key(key)) => parent.object_mut()?.push(Some(key), value),
+            (None, PathSegment::Index(_)) => {
+                parent.array_mut()?; // WrongType if it isn't an array at all
+                return Err(EditError::NotFound);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a `key` member at the end of the object at `path`, or replaces
+    /// its value if the object already has one.
+    pub fn insert_key(
+        &mut self,
+        path: &[PathSegment<'_>],
+        key: &str,
+        value: &JsonValue<'_>,
+    ) -> Result<(), EditError> {
+        let node = self.get_mut(path).ok_or(EditError::NotFound)?;
+        match node.get_mut(PathSegment::Key(key)) {
+            Some(node) => node.replace(value),
+            None => node.object_mut()?.push(Some(key), value),
+        }
+        Ok(())
+    }
+
+    /// Inserts `value` into the array at `path` before `index`, or at the
+    /// end if `index` is its length.
+    pub fn insert(
+        &mut self,
+        path: &[PathSegment<'_>],
+        index: usize,
+        value: &JsonValue<'_>,
+    ) -> Result<(), EditError> {
+        let array = self.get_mut(path).ok_or(EditError::NotFound)?.array_mut()?;
+        if index > array.items.len() {
+            return Err(EditError::NotFound);
+        }
+        if index == array.items.len() {
+            array.push(None, value);
+            return Ok(());
+        }
+        let item = Item {
+            leading: indentation(&array.items[index].leading).to_string(),
+            key: None,
+            value: Node::from_value(value),
+            before_comma: String::new(),
+            comma: true,
+            trailing: String::new(),
+        };
+        if index == 0 && array.items[0].leading.trim().is_empty() {
+            // the old first element moves down to where a second one sits
+            let spacing = array
+                .items
+                .get(1)
+                .map_or(" ", |next| indentation(&next.leading));
+            array.items[0].leading = spacing.to_string();
+        }
+        array.items.insert(index, item);
+        Ok(())
+    }
+
+    /// Removes the array element or object member at `path`, along with the
+    /// comments attached to it, and returns its value.
+    pub fn remove(&mut self, path: &[PathSegment<'_>]) -> Result<JsonValue<'static>, EditError> {
+        let (&last, parent) = path.split_last().ok_or(EditError::EmptyPath)?;
+        let parent = self.get_mut(parent).ok_or(EditError::NotFound)?;
+        let index = match parent.position(last) {
+            Some(index) => index,
+            None => {
+                return Err(match (last, &parent.kind) {
+                    (PathSegment::Key(_), Kind::Object(_))
+                    | (PathSegment::Index(_), Kind::Array(_)) => EditError::NotFound,
+                    _ => EditError::WrongType,
+                })
+            }
+        };
+        let container = parent.container_mut().ok_or(EditError::WrongType)?;
+        let removed = container.items.remove(index);
+
+        if index == container.items.len() {
+            // the last item went, so the one before it takes over its comma,
+            // or lack of one
+            match container.items.last_mut() {
+                Some(prev) if !removed.comma => {
+                    prev.comma = false;
+                    prev.before_comma.clear();
+                }
+                Some(_) => {}
+                None if container.close_leading.trim().is_empty() => {
+                    container.close_leading.clear();
+                }
+                None => {}
+            }
+        } else if index == 0 && !container.items[0].leading.contains('\n') {
+            // on a single line, keep the spacing after the opening bracket
+            container.items[0].leading = indentation(&removed.leading).to_string();
+        }
+        Ok(removed.value.to_value())
+    }
+}
+
+/// The document exactly as parsed, plus any edits.
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.leading, self.root, self.trailing)
+    }
+}
+
+impl Node {
+    /// The array element or object member `segment` names. If an object
+    /// repeats the key, this is the last member with it.
+    pub fn get(&self, segment: PathSegment<'_>) -> Option<&Node> {
+        self.item(segment).map(|item| &item.value)
+    }
+
+    pub fn get_mut(&mut self, segment: PathSegment<'_>) -> Option<&mut Node> {
+        let index = self.position(segment)?;
+        let container = self.container_mut()?;
+        Some(&mut container.items[index].value)
+    }
+
+    /// Replaces this node with `value`, written compactly. Comments and
+    /// whitespace around the node are kept.
+    pub fn replace(&mut self, value: &JsonValue<'_>) {
+        *self = Node::from_value(value);
+    }
+
+    /// The node as a `JsonValue`, dropping its formatting and comments.
+    pub fn to_value(&self) -> JsonValue<'static> {
+        match &self.kind {
+            Kind::Scalar { value, .. } => value.clone(),
+            Kind::Array(container) => JsonValue::Array(
+                container
+                    .items
+                    .iter()
+                    .map(|item| item.value.to_value())
+                    .collect(),
+            ),
+            Kind::Object(container) => {
+                let mut map = Map::new();
+                for item in &container.items {
+                    if let Some(key) = &item.key {
+                        map.insert(key.name.clone(), item.value.to_value());
+                    }
+                }
+                JsonValue::Object(map)
+            }
+        }
+    }
+
+    /// A compact node for `value`, as the serializer would write it. NaN
+    /// and the infinities are written, and read back, as `null`.
+    fn from_value(value: &JsonValue<'_>) -> Node {
+        let kind = match value {
+            JsonValue::Array(arr) => {
+                Kind::Array(Container::compact(arr.iter().map(|value| (None, value))))
+            }
+            JsonValue::Object(map) => Kind::Object(Container::compact(
+                map.iter().map(|(key, value)| (Some(key), value)),
+            )),
+            JsonValue::Number(n) if !n.is_finite() => Kind::Scalar {
+                raw: "null".to_string(),
+                value: JsonValue::Null,
+            },
+            _ => Kind::Scalar {
+                raw: value.to_string(),
+                value: value.clone().into_owned(),
+            },
+        };
+        Node { kind }
+    }
+
+    fn item(&self, segment: PathSegment<'_>) -> Option<&Item> {
+        let index = self.position(segment)?;
+        match &self.kind {
+            Kind::Array(container) | Kind::Object(container) => container.items.get(index),
+            Kind::Scalar { .. } => None,
+        }
+    }
+
+    fn position(&self, segment: PathSegment<'_>) -> Option<usize> {
+        match (&self.kind, segment) {
+            (Kind::Array(container), PathSegment::Index(index)) => {
+                (index < container.items.len()).then_some(index)
+            }
+            (Kind::Object(container), PathSegment::Key(name)) => container
+                .items
+                .iter()
+                .rposition(|item| item.key.as_ref().is_some_and(|key| key.name == name)),
+            _ => None,
+        }
+    }
+
+    fn container_mut(&mut self) -> Option<&mut Container> {
+        match &mut self.kind {
+            Kind::Array(container) | Kind::Object(container) => Some(container),
+            Kind::Scalar { .. } => None,
+        }
+    }
+
+    fn object_mut(&mut self) -> Result<&mut Container, EditError> {
+        match &mut self.kind {
+            Kind::Object(container) => Ok(container),
+            _ => Err(EditError::WrongType),
+        }
+    }
+
+    fn array_mut(&mut self) -> Result<&mut Container, EditError> {
+        match &mut self.kind {
+            Kind::Array(container) => Ok(container),
+            _ => Err(EditError::WrongType),
+        }
+    }
+}
+
+impl Container {
+    /// A container written without any whitespace, `key` being `Some` in
+    /// objects.
+    fn compact<'v>(items: impl Iterator<Item = (Option<&'v str>, &'v JsonValue<'v>)>) -> Container {
+        let mut items: Vec<Item> = items
+            .map(|(key, value)| Item {
+                leading: String::new(),
+                key: key.map(|name| Key {
+                    name: name.to_string(),
+                    raw: quoted(name),
+                    before_colon: String::new(),
+                    after_colon: String::new(),
+                }),
+                value: Node::from_value(value),
+                before_comma: String::new(),
+                comma: true,
+                trailing: String::new(),
+            })
+            .collect();
+        if let Some(last) = items.last_mut() {
+            last.comma = false;
+        }
+        Container {
+            items,
+            close_leading: String::new(),
+        }
+    }
+
+    /// Appends an item, spaced like the current last one. `key` must be
+    /// `Some` exactly when this is an object.
+    fn push(&mut self, key: Option<&str>, value: &JsonValue<'_>) {
+        let last = self.items.last_mut();
+        let mut leading = last
+            .as_ref()
+            .map_or("", |last| indentation(&last.leading))
+            .to_string();
+        let after_line_comment = last.as_ref().is_some_and(|last| {
+            comments_in(&last.trailing)
+                .last()
+                .is_some_and(|comment| comment.starts_with("//"))
+        });
+        if after_line_comment && !leading.contains('\n') {
+            // the new item can't go on the same line as the comment
+            leading = format!("{}{}", indentation(&self.close_leading), leading);
+            if !leading.contains('\n') {
+                leading.insert(0, '\n');
+            }
+        }
+        let key = key.map(|name| {
+            let spacing = last.as_ref().and_then(|last| last.key.as_ref());
+            Key {
+                name: name.to_string(),
+                raw: quoted(name),
+                before_colon: spacing
+                    .map_or("", |key| whitespace(&key.before_colon))
+                    .to_string(),
+                after_colon: spacing
+                    .map_or("", |key| whitespace(&key.after_colon))
+                    .to_string(),
+            }
+        });
+        let mut item = Item {
+            leading,
+            key,
+            value: Node::from_value(value),
+            before_comma: String::new(),
+            comma: false,
+            trailing: String::new(),
+        };
+        if let Some(last) = last {
+            // keep a JSON5 trailing comma at the end, otherwise add one
+            // between the old last item and the new one
+            item.comma = last.comma;
+            last.comma = true;
+        }
+        self.items.push(item);
+    }
+}
+
+/// `name` as a JSON string, quotes included.
+fn quoted(name: &str) -> String {
+    let mut raw = String::new();
+    ser::write_string(&mut raw, name, false).expect("writing to a String cannot fail");
+    raw
+}
+
+/// The whitespace at the end of some leading trivia: the line break and
+/// indentation in front of an item, without any comments above it.
+fn indentation(leading: &str) -> &str {
+    &leading[leading.trim_end().len()..]
+}
+
+/// `trivia` if it is only whitespace, otherwise nothing.
+fn whitespace(trivia: &str) -> &str {
+    if trivia.trim().is_empty() {
+        trivia
+    } else {
+        ""
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (container, open, close) = match &self.kind {
+            Kind::Scalar { raw, .. } => return f.write_str(raw),
+            Kind::Array(container) => (container, '[', ']'),
+            Kind::Object(container) => (container, '{', '}'),
+        };
+        write!(f, "{}", open)?;
+        for item in &container.items {
+            f.write_str(&item.leading)?;
+            if let Some(key) = &item.key {
+                write!(f, "{}{}:{}", key.raw, key.before_colon, key.after_colon)?;
+            }
+            write!(f, "{}", item.value)?;
+            if item.comma {
+                write!(f, "{},", item.before_comma)?;
+            }
+            f.write_str(&item.trailing)?;
+        }
+        write!(f, "{}{}", container.close_leading, close)
+    }
+}
+
+/// Builds the tree from a `Parser`'s tokens, taking the trivia from the
+/// input between one token's span and the next.
+struct Builder<'a> {
+    input: &'a str,
+    parser: Parser<'a>,
+}
+
+impl Builder<'_> {
+    /// The whitespace and comments between the last token and the next one.
+    fn trivia(&mut self) -> Result<String, JsonError> {
+        let start = self.parser.peek()?.span.start;
+        Ok(self.input[self.parser.consumed..start].to_string())
+    }
+
+    fn node(&mut self) -> Result<Node, JsonError> {
+        let kind = match self.parser.current_token()? {
+            Some(token @ (Token::LBrace | Token::LBracket)) => {
+                let object = *token == Token::LBrace;
+                self.parser.count_node()?;
+                self.parser.enter()?;
+                let container = self.container(object)?;
+                self.parser.depth -= 1;
+                if object {
+                    Kind::Object(container)
+                } else {
+                    Kind::Array(container)
+                }
+            }
+            _ => {
+                let start = self.parser.peek()?.span.start;
+                // a scalar, or an error `parse_value` reports
+                let value = self.parser.parse_value_with(owned)?;
+                Kind::Scalar {
+                    raw: self.input[start..self.parser.consumed].to_string(),
+                    value,
+                }
+            }
+        };
+        Ok(Node { kind })
+    }
+
+    /// Follows `Parser::parse_object`/`parse_array`, from the '{' or '['
+    /// under the cursor.
+    fn container(&mut self, object: bool) -> Result<Container, JsonError> {
+        let (close, expected_after) = if object {
+            (Token::RBrace, Expected::CommaOrRBrace)
+        } else {
+            (Token::RBracket, Expected::CommaOrRBracket)
+        };
+        let trailing_commas = self.parser.options.dialect == Dialect::Json5;
+        self.parser.advance(); // consume '{' or '['
+
+        let mut items = Vec::new();
+        let mut leading = self.trivia()?;
+        if self.parser.current_token()? == Some(&close) {
+            self.parser.advance();
+            return Ok(Container {
+                items,
+                close_leading: leading,
+            });
+        }
+
+        let mut expected_key = Expected::KeyOrRBrace;
+        loop {
+            let kind = if object {
+                LimitKind::ObjectMembers
+            } else {
+                LimitKind::ArrayLength
+            };
+            self.parser.check_len(kind, items.len())?;
+            let key = if object {
+                let span = self.parser.peek()?.span;
+                let name = match self.parser.take_key()? {
+                    Some(name) => name.into_owned(),
+                    None => return Err(self.parser.unexpected(expected_key)),
+                };
+                expected_key = Expected::Key;
+                let raw = self.input[span.start..span.end].to_string();
+                let before_colon = self.trivia()?;
+                match self.parser.current_token()? {
+                    Some(Token::Colon) => self.parser.advance(),
+                    _ => return Err(self.parser.unexpected(Expected::Colon)),
+                }
+                let after_colon = self.trivia()?;
+                Some(Key {
+                    name,
+                    raw,
+                    before_colon,
+                    after_colon,
+                })
+            } else {
+                None
+            };
+
+            let value = self.node()?;
+            let after_value = self.trivia()?;
+            let comma = match self.parser.current_token()? {
+                Some(Token::Comma) => true,
+                Some(token) if *token == close => false,
+                _ => return Err(self.parser.unexpected(expected_after)),
+            };
+            self.parser.advance(); // consume ',' or the closing bracket
+            let (before_comma, gap) = if comma {
+                (after_value, self.trivia()?)
+            } else {
+                (String::new(), after_value)
+            };
+            let (trailing, rest) = split_trailing(gap);
+            items.push(Item {
+                leading,
+                key,
+                value,
+                before_comma,
+                comma,
+                trailing,
+            });
+
+            if !comma {
+                return Ok(Container {
+                    items,
+                    close_leading: rest,
+                });
+            }
+            if trailing_commas && self.parser.current_token()? == Some(&close) {
+                self.parser.advance();
+                return Ok(Container {
+                    items,
+                    close_leading: rest,
+                });
+            }
+            leading = rest;
+        }
+    }
+}
+
+/// Splits the trivia after an item into the comments on the rest of its
+/// line, which belong to that item, and everything after them.
+fn split_trailing(gap: String) -> (String, String) {
+    let mut end = 0;
+    let mut has_comment = false;
+    loop {
+        let rest = &gap[end..];
+        if let Some(len) = comment_len(rest) {
+            end += len;
+            has_comment = true;
+        } else if rest.starts_with([' ', '\t']) {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    if !has_comment {
+        return (String::new(), gap);
+    }
+    let mut trailing = gap;
+    let rest = trailing.split_off(end);
+    (trailing, rest)
+}
+
+/// The comments in a stretch of trivia.
+fn comments_in(trivia: &str) -> Vec<&str> {
+    let mut comments = Vec::new();
+    let mut rest = trivia;
+    while let Some(start) = rest.find('/') {
+        rest = &rest[start..];
+        let len = comment_len(rest).unwrap_or(rest.len());
+        comments.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    comments
+}
+
+/// The length of the comment `rest` starts with, if it starts with one. A
+/// line comment stops before the line break.
+fn comment_len(rest: &str) -> Option<usize> {
+    if let Some(body) = rest.strip_prefix("//") {
+        return Some(2 + body.find(['\r', '\n']).unwrap_or(body.len()));
+    }
+    let body = rest.strip_prefix("/*")?;
+    Some(2 + body.find("*/").map_or(body.len(), |close| close + 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_json_str, parse_json_str_with_options};
+
+    fn jsonc() -> ParserOptions {
+        ParserOptions {
+            dialect: Dialect::Jsonc,
+            ..ParserOptions::default()
+        }
+    }
+
+    fn value(input: &str) -> JsonValue<'static> {
+        parse_json_str(input).unwrap()
+    }
+
+    fn parse(input: &str) -> Document {
+        Document::parse_with_options(input, jsonc()).unwrap()
+    }
+
+    /// The edited text, checked to still parse to the same value.
+    fn edited(doc: &Document) -> String {
+        let text = doc.to_string();
+        let reparsed = parse_json_str_with_options(&text, jsonc())
+            .unwrap_or_else(|e| panic!("{:?} no longer parses: {}", text, e));
+        assert_eq!(reparsed, doc.root().to_value());
+        text
+    }
+
+    #[test]
+    fn round_trips_byte_for_byte() {
+        for input in [
+            "1",
+            " \"a\\u00e9\" ",
+            "// header\n{\n  \"a\": 1.50, // one\n  /* b */ \"b\": [1 , 2,3]\n}\n",
+            "{ \"a\" /* k */ : /* v */ 1 }",
+            "[\n  1 // c\n  , 2\n]",
+            "{}",
+            "[ ]",
+        ] {
+            assert_eq!(parse(input).to_string(), input);
+        }
+    }
+
+    #[test]
+    fn attaches_comments_to_nearest_item() {
+        let doc = parse("{\n  // above\n  \"a\": 1, // after\n  \"b\": 2\n}");
+        assert_eq!(
+            doc.comments(&["a".into()]).unwrap(),
+            ["// above", "// after"]
+        );
+        assert!(doc.comments(&["b".into()]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn insert_key_after_line_comment() {
+        let mut doc = parse("{ \"a\": 1 // c\n}");
+        doc.insert_key(&[], "b", &value("2")).unwrap();
+        assert_eq!(edited(&doc), "{ \"a\": 1, // c\n \"b\": 2\n}");
+
+        let mut doc = parse("{\n  \"a\": 1 // c\n}");
+        doc.set(&["b".into()], &value("2")).unwrap();
+        assert_eq!(edited(&doc), "{\n  \"a\": 1, // c\n  \"b\": 2\n}");
+    }
+
+    #[test]
+    fn insert_after_line_comment() {
+        let mut doc = parse("[1, 2 // c\n]");
+        doc.insert(&[], 2, &value("3")).unwrap();
+        assert_eq!(edited(&doc), "[1, 2, // c\n 3\n]");
+    }
+
+    #[test]
+    fn edits_keep_formatting() {
+        let mut doc = parse("{\n  \"a\": 1, // one\n  \"b\": [1, 2]\n}");
+        doc.set(&["a".into()], &value("\"x\"")).unwrap();
+        doc.insert(&["b".into()], 0, &value("0")).unwrap();
+        doc.insert_key(&[], "c", &value(r#"{"d": null}"#)).unwrap();
+        assert_eq!(
+            edited(&doc),
+            "{\n  \"a\": \"x\", // one\n  \"b\": [0, 1, 2],\n  \"c\": {\"d\":null}\n}"
+        );
+
+        assert_eq!(doc.remove(&["b".into(), 1.into()]), Ok(value("1")));
+        assert_eq!(doc.remove(&["a".into()]), Ok(value("\"x\"")));
+        assert_eq!(
+            edited(&doc),
+            "{\n  \"b\": [0, 2],\n  \"c\": {\"d\":null}\n}"
+        );
+    }
+
+    #[test]
+    fn set_value_deeper_than_max_depth() {
+        let mut deep = value("1");
+        for _ in 0..200 {
+            deep = JsonValue::Array(vec![deep]);
+        }
+        let mut doc = parse("{\"a\": 1}");
+        doc.set(&["a".into()], &deep).unwrap();
+        assert_eq!(doc.get(&["a".into()]).unwrap().to_value(), deep);
+        let text = doc.to_string();
+        assert_eq!(text.len(), "{\"a\": }".len() + 401);
+    }
+
+    #[test]
+    fn edit_errors() {
+        let mut doc = parse("{\"a\": [1]}");
+        assert_eq!(doc.remove(&[]), Err(EditError::EmptyPath));
+        assert_eq!(doc.remove(&["x".into()]), Err(EditError::NotFound));
+        assert_eq!(
+            doc.insert(&["a".into()], 5, &value("1")),
+            Err(EditError::NotFound)
+        );
+        assert_eq!(
+            doc.insert_key(&["a".into()], "k", &value("1")),
+            Err(EditError::WrongType)
+        );
+        assert_eq!(doc.to_string(), "{\"a\": [1]}");
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+
+use crate::{JsonError, LexError, ParseError, Span};
+
+/// An error message tied to a region of the input, which can be rendered
+/// rustc-style with the offending line and a `^^^` underline:
+///
+/// ```text
+/// error: expected ',' or '}' after object member, found ':'
+///  --> 3:14
+///   |
+/// 3 |     "age": 30: 31,
+///   |              ^
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Renders the diagnostic against `source`, the input the span refers to.
+    pub fn render(&self, source: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+
+        // keep tabs in the padding so the caret lines up with the source line
+        let padding: String = source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        // a span running past the end of the line (an unterminated string) is
+        // cut off there; a zero-width span (end of input) still gets one caret
+        let end = self.span.end.min(line_start + line.len()).max(start);
+        let width = source[start..end].chars().count().max(1);
+
+        let line_number = self.span.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+
+        format!(
+            "error: {message}\n\
+             {gutter}--> {span}\n\
+             {gutter} |\n\
+             {line_number} | {line}\n\
+             {gutter} | {padding}{carets}",
+            message = self.message,
+            span = self.span,
+            carets = "^".repeat(width),
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error: {} at {}", self.message, self.span)
+    }
+}
+
+impl From<&LexError> for Diagnostic {
+    fn from(e: &LexError) -> Self {
+        Diagnostic::new(e.message(), e.span())
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(e: &ParseError) -> Self {
+        Diagnostic::new(e.message(), e.span())
+    }
+}
+
+impl From<&JsonError> for Diagnostic {
+    fn from(e: &JsonError) -> Self {
+        Diagnostic::new(e.message(), e.span())
+    }
+}
+
+impl From<JsonError> for Diagnostic {
+    fn from(e: JsonError) -> Self {
+        Diagnostic::from(&e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json_str;
+
+    fn render(source: &str, start: usize, end: usize, line: usize, column: usize) -> String {
+        let span = Span {
+            start,
+            end,
+            line,
+            column,
+        };
+        Diagnostic::new("oops", span).render(source)
+    }
+
+    #[test]
+    fn renders_the_line_with_carets() {
+        let source = "{\n  \"age\": 30: 31\n}";
+        let e = parse_json_str(source).unwrap_err();
+        let expected = [
+            "error: expected ',' or '}' after object member, found ':'",
+            " --> 2:12",
+            "  |",
+            "2 |   \"age\": 30: 31",
+            "  |            ^",
+        ];
+        assert_eq!(Diagnostic::from(&e).render(source), expected.join("\n"));
+        assert_eq!(
+            render("[\"abc\"]", 1, 6, 1, 2),
+            "error: oops\n --> 1:2\n  |\n1 | [\"abc\"]\n  |  ^^^^^"
+        );
+    }
+
+    #[test]
+    fn tabs_before_the_error_are_kept() {
+        let rendered = render("{\n\t\t\"a\": x}", 8, 9, 2, 7);
+        assert!(
+            rendered.ends_with("2 | \t\t\"a\": x}\n  | \t\t    ^"),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn zero_width_span_at_the_end() {
+        let rendered = render("[1, 2", 5, 5, 1, 6);
+        assert!(rendered.ends_with("1 | [1, 2\n  |      ^"), "{}", rendered);
+        // after a final line break, the empty last line is shown
+        let rendered = render("[1,\n", 4, 4, 2, 1);
+        assert!(rendered.ends_with("2 | \n  | ^"), "{}", rendered);
+    }
+
+    #[test]
+    fn span_across_lines_is_cut_at_the_line_end() {
+        let rendered = render("[\"abc\ndef\"]", 1, 10, 1, 2);
+        assert!(rendered.ends_with("1 | [\"abc\n  |  ^^^^"), "{}", rendered);
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let source = "{\r\n  \"a\": x\r\n}";
+        let rendered = render(source, 10, 11, 2, 8);
+        assert!(
+            rendered.ends_with("2 |   \"a\": x\n  |        ^"),
+            "{}",
+            rendered
+        );
+        // a span from the '\r' or '\n' of a line break
+        let rendered = render(source, 11, 13, 2, 9);
+        assert!(
+            rendered.ends_with("2 |   \"a\": x\n  |         ^"),
+            "{}",
+            rendered
+        );
+        let rendered = render(source, 12, 13, 2, 10);
+        assert!(
+            rendered.ends_with("2 |   \"a\": x\n  |          ^"),
+            "{}",
+            rendered
+        );
+    }
+}
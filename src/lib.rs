@@ -0,0 +1,2036 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::vec;
+
+pub mod convert;
+pub mod cst;
+pub mod diagnostic;
+pub mod map;
+pub mod ndjson;
+pub mod number;
+pub mod reader;
+pub mod recovery;
+pub mod ser;
+pub mod stream;
+pub mod value;
+pub mod writer;
+
+use map::{Map, MapBackend};
+use number::JsonNumber;
+
+/// A parsed JSON value. Strings and object keys are `Cow`s so that a borrowed
+/// parse (`parse_json_borrowed`) can point into the input for strings without
+/// escapes; `JsonValue<'static>` owns all of its data.
+///
+/// `JsonValue` implements `Drop` so that deeply nested values don't overflow
+/// the stack, which means its contents can't be moved out by a pattern such
+/// as `match value { JsonValue::String(s) => s, .. }`. Match on `&mut value`
+/// and `mem::take` the contents instead, or convert with `TryFrom`, e.g.
+/// `String::try_from(value)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue<'a> {
+    Null,
+    Bool(bool),
+    Number(JsonNumber),
+    String(Cow<'a, str>),
+    Array(Vec<JsonValue<'a>>),
+    Object(Map<'a>),
+}
+
+impl JsonValue<'_> {
+    /// Copies any strings borrowed from the input, detaching the value from it.
+    /// Like dropping, this works from an explicit stack rather than by
+    /// recursion, so it is safe however deeply the value is nested.
+    pub fn into_owned(self) -> JsonValue<'static> {
+        let mut stack = Vec::new();
+        let mut next = self;
+        loop {
+            let mut done = match &mut next {
+                JsonValue::Null => Some(JsonValue::Null),
+                JsonValue::Bool(b) => Some(JsonValue::Bool(*b)),
+                JsonValue::Number(n) => Some(JsonValue::Number(n.clone())),
+                JsonValue::String(s) => {
+                    Some(JsonValue::String(Cow::Owned(mem::take(s).into_owned())))
+                }
+                JsonValue::Array(arr) => {
+                    let out = Vec::with_capacity(arr.len());
+                    stack.push(Owning::Array(mem::take(arr).into_iter(), out));
+                    None
+                }
+                JsonValue::Object(map) => {
+                    let out = Map::with_backend(map.backend());
+                    stack.push(Owning::Object(
+                        mem::take(map).into_iter(),
+                        out,
+                        Cow::Borrowed(""),
+                    ));
+                    None
+                }
+            };
+            // hand the finished value to the innermost open container, and
+            // move on to that container's next item or close it
+            loop {
+                match stack.last_mut() {
+                    None => return done.expect("only the root is finished with nothing open"),
+                    Some(Owning::Array(items, out)) => {
+                        out.extend(done.take());
+                        if let Some(item) = items.next() {
+                            next = item;
+                            break;
+                        }
+                        done = Some(JsonValue::Array(mem::take(out)));
+                    }
+                    Some(Owning::Object(members, out, key)) => {
+                        if let Some(value) = done.take() {
+                            out.append(mem::take(key), value);
+                        }
+                        if let Some((k, value)) = members.next() {
+                            *key = Cow::Owned(k.into_owned());
+                            next = value;
+                            break;
+                        }
+                        done = Some(JsonValue::Object(mem::take(out)));
+                    }
+                }
+                stack.pop();
+            }
+        }
+    }
+}
+
+/// A container `JsonValue::into_owned` is part way through copying: the items
+/// still to copy, the copy so far, and for objects the key of the member
+/// being copied.
+enum Owning<'a> {
+    Array(vec::IntoIter<JsonValue<'a>>, Vec<JsonValue<'static>>),
+    Object(map::IntoIter<'a>, Map<'static>, Cow<'static, str>),
+}
+
+/// Takes nested arrays and objects apart from an explicit stack instead of
+/// recursing into them, so dropping a value nested thousands of levels deep
+/// can't overflow the stack.
+impl Drop for JsonValue<'_> {
+    fn drop(&mut self) {
+        let mut stack = match self {
+            JsonValue::Array(arr) if !arr.is_empty() => mem::take(arr),
+            JsonValue::Object(map) if !map.is_empty() => {
+                mem::take(map).into_iter().map(|(_, value)| value).collect()
+            }
+            _ => return,
+        };
+        while let Some(mut value) = stack.pop() {
+            match &mut value {
+                JsonValue::Array(arr) => stack.append(arr),
+                JsonValue::Object(map) => {
+                    stack.extend(mem::take(map).into_iter().map(|(_, value)| value))
+                }
+                _ => {}
+            }
+            // `value` has no children left, so dropping it doesn't recurse
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token<'a> {
+    LBrace,                   // '{'
+    RBrace,                   // '}'
+    LBracket,                 // '['
+    RBracket,                 // ']'
+    Colon,                    // ':'
+    Comma,                    // ','
+    String(Cow<'a, str>),     // e.g. "hello", borrowed from the input unless it has escapes
+    Number(Cow<'a, str>),     // e.g. "123", "3.14", "-2e10"
+    Identifier(Cow<'a, str>), // an unquoted object key, JSON5 only
+    True,                     // true
+    False,                    // false
+    Null,                     // null
+    Eof,                      // end of input
+}
+
+impl Token<'_> {
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::LBrace => Token::LBrace,
+            Token::RBrace => Token::RBrace,
+            Token::LBracket => Token::LBracket,
+            Token::RBracket => Token::RBracket,
+            Token::Colon => Token::Colon,
+            Token::Comma => Token::Comma,
+            Token::String(s) => Token::String(Cow::Owned(s.into_owned())),
+            Token::Number(n) => Token::Number(Cow::Owned(n.into_owned())),
+            Token::Identifier(s) => Token::Identifier(Cow::Owned(s.into_owned())),
+            Token::True => Token::True,
+            Token::False => Token::False,
+            Token::Null => Token::Null,
+            Token::Eof => Token::Eof,
+        }
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LBrace => write!(f, "'{{'"),
+            Token::RBrace => write!(f, "'}}'"),
+            Token::LBracket => write!(f, "'['"),
+            Token::RBracket => write!(f, "']'"),
+            Token::Colon => write!(f, "':'"),
+            Token::Comma => write!(f, "','"),
+            Token::String(s) => write!(f, "string {:?}", s),
+            Token::Number(n) => write!(f, "number {}", n),
+            Token::Identifier(s) => write!(f, "identifier {}", s),
+            Token::True => write!(f, "'true'"),
+            Token::False => write!(f, "'false'"),
+            Token::Null => write!(f, "'null'"),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+/// A region of the input: the byte range `start..end`, plus the 1-based line
+/// and column (counted in chars) where it begins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken<'a> {
+    pub token: Token<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum LexError {
+    InvalidToken(char, Span),     // unrecognized character or word
+    UnterminatedString(Span),     // string not closed properly, from the opening quote
+    InvalidEscape(char, Span),    // unknown escape such as '\x'
+    ControlCharacter(char, Span), // unescaped U+0000 to U+001F in a string
+    InvalidUnicodeEscape(Span),   // bad '\uXXXX' hex or lone surrogate
+    InvalidNumber(Span),          // number not matching the JSON grammar, e.g. '01' or '1.'
+    UnterminatedComment(Span),    // JSON5/JSONC '/*' without '*/'
+    StringTooLong {
+        limit: usize, // `ParserOptions::max_string_length`
+        span: Span,   // from the opening quote to where the limit was passed
+    },
+    InvalidUtf8 {
+        offset: usize, // byte offset of the first bad byte
+        span: Span,    // the whole bad sequence
+    },
+}
+
+impl LexError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::InvalidToken(_, span)
+            | LexError::UnterminatedString(span)
+            | LexError::InvalidEscape(_, span)
+            | LexError::ControlCharacter(_, span)
+            | LexError::InvalidUnicodeEscape(span)
+            | LexError::InvalidNumber(span)
+            | LexError::UnterminatedComment(span)
+            | LexError::StringTooLong { span, .. }
+            | LexError::InvalidUtf8 { span, .. } => *span,
+        }
+    }
+
+    /// The error text without its position.
+    pub fn message(&self) -> String {
+        match self {
+            LexError::InvalidToken(ch, _) => format!("invalid token '{}'", ch),
+            LexError::UnterminatedString(_) => "unterminated string".to_string(),
+            LexError::InvalidEscape(ch, _) => format!("invalid escape '\\{}'", ch),
+            LexError::ControlCharacter(ch, _) => {
+                format!("unescaped control character U+{:04X} in string", *ch as u32)
+            }
+            LexError::InvalidUnicodeEscape(_) => "invalid unicode escape".to_string(),
+            LexError::InvalidNumber(_) => "invalid number".to_string(),
+            LexError::UnterminatedComment(_) => "unterminated comment".to_string(),
+            LexError::StringTooLong { limit, .. } => {
+                format!("string longer than {} bytes", limit)
+            }
+            LexError::InvalidUtf8 { offset, .. } => {
+                format!("invalid UTF-8 (byte offset {})", offset)
+            }
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message(), self.span())
+    }
+}
+
+impl Error for LexError {}
+
+/// Walks UTF-8 input one char at a time, keeping track of the byte offset,
+/// line and column of the next char. A leading byte order mark is skipped.
+///
+/// Invalid UTF-8 comes out as U+FFFD, one per maximal bad sequence like
+/// `String::from_utf8_lossy`, and the first one is remembered in
+/// `invalid_utf8` for the lexer to report.
+struct Cursor<'a> {
+    input: &'a [u8],
+    offset: usize,
+    line: usize,
+    column: usize,
+    invalid_utf8: Option<Span>,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Cursor {
+            input,
+            offset: if input.starts_with(b"\xEF\xBB\xBF") {
+                3
+            } else {
+                0
+            },
+            line: 1,
+            column: 1,
+            invalid_utf8: None,
+        }
+    }
+
+    /// The next char, its length in bytes, and whether it was valid UTF-8.
+    fn decode(&self) -> Option<(char, usize, bool)> {
+        let rest = &self.input[self.offset..];
+        let first = *rest.first()?;
+        if first.is_ascii() {
+            return Some((first as char, 1, true));
+        }
+        // no char is longer than 4 bytes, so there is no need to look further
+        let chunk = rest[..rest.len().min(4)].utf8_chunks().next()?;
+        match chunk.valid().chars().next() {
+            Some(c) => Some((c, c.len_utf8(), true)),
+            None => Some((char::REPLACEMENT_CHARACTER, chunk.invalid().len(), false)),
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.decode().map(|(c, _, _)| c)
+    }
+
+    fn offset(&mut self) -> usize {
+        self.offset
+    }
+
+    /// A zero-width span at the current position.
+    fn mark(&mut self) -> Span {
+        let offset = self.offset();
+        Span {
+            start: offset,
+            end: offset,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    /// The span from `start` up to the current position.
+    fn span_from(&mut self, start: Span) -> Span {
+        Span {
+            end: self.offset(),
+            ..start
+        }
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let (c, len, valid) = self.decode()?;
+        if !valid && self.invalid_utf8.is_none() {
+            let start = self.mark();
+            self.invalid_utf8 = Some(Span {
+                end: start.start + len,
+                ..start
+            });
+        }
+        self.offset += len;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+}
+
+/// Turns the input into tokens one at a time. As an iterator it yields every
+/// token up to and including the final `Eof`. After an error it carries on
+/// after the offending input.
+pub struct Lexer<'a> {
+    cursor: Cursor<'a>,
+    lossy_utf8: bool,
+    dialect: Dialect,
+    max_string_length: usize,
+    finished: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer::with_options(input, &ParserOptions::default())
+    }
+
+    /// A lexer for `options.dialect`. A string longer than
+    /// `options.max_string_length` is a `LexError::StringTooLong` as soon as
+    /// it gets there, without reading the rest of it. The other options are
+    /// for the parser.
+    pub fn with_options(input: &'a str, options: &ParserOptions) -> Self {
+        Lexer::from_bytes_with_options(input.as_bytes(), options)
+    }
+
+    /// Lexes raw bytes, checking that they are UTF-8 along the way. A bad
+    /// sequence is a `LexError::InvalidUtf8`, or with `ParserOptions::lossy_utf8`
+    /// is read as U+FFFD instead, which is only allowed inside strings.
+    pub fn from_bytes(input: &'a [u8]) -> Self {
+        Lexer::from_bytes_with_options(input, &ParserOptions::default())
+    }
+
+    pub fn from_bytes_with_options(input: &'a [u8], options: &ParserOptions) -> Self {
+        Lexer {
+            cursor: Cursor::new(input),
+            lossy_utf8: options.lossy_utf8,
+            dialect: options.dialect,
+            max_string_length: options.max_string_length,
+            finished: false,
+        }
+    }
+
+    /// The next token, or `Eof` (again on every call) once the input is used up.
+    pub fn next_token(&mut self) -> Result<SpannedToken<'a>, LexError> {
+        let result = self.lex_token();
+        match self.cursor.invalid_utf8.take() {
+            Some(span) if !self.lossy_utf8 => Err(LexError::InvalidUtf8 {
+                offset: span.start,
+                span,
+            }),
+            _ => result,
+        }
+    }
+
+    fn lex_token(&mut self) -> Result<SpannedToken<'a>, LexError> {
+        let input = self.cursor.input;
+        let json5 = self.dialect == Dialect::Json5;
+        let comments = json5 || self.dialect == Dialect::Jsonc;
+        let max_string_length = self.max_string_length;
+        let cursor = &mut self.cursor;
+
+        loop {
+            let start = cursor.mark();
+            let ch = match cursor.next() {
+                Some(ch) => ch,
+                None => {
+                    return Ok(SpannedToken {
+                        token: Token::Eof,
+                        span: start,
+                    })
+                }
+            };
+
+            let token = match ch {
+                // whitespace (ignore)
+                ' ' | '\n' | '\t' | '\r' => continue,
+
+                // JSON5 also allows any Unicode whitespace, JSON5 and JSONC comments
+                c if json5 && (c.is_whitespace() || c == '\u{feff}') => continue,
+                '/' if comments => {
+                    skip_comment(cursor, start)?;
+                    continue;
+                }
+
+                // single character tokens
+                '{' => Token::LBrace,
+                '}' => Token::RBrace,
+                '[' => Token::LBracket,
+                ']' => Token::RBracket,
+                ':' => Token::Colon,
+                ',' => Token::Comma,
+
+                // start of a string, which JSON5 also allows in single quotes
+                quote @ ('"' | '\'') if quote == '"' || json5 => {
+                    let content_start = cursor.offset();
+                    let mut content_end = content_start;
+                    // stays `None`, and the string is borrowed from the input,
+                    // until the first escape
+                    let mut owned: Option<String> = None;
+                    let mut terminated = false;
+
+                    loop {
+                        let len = match &owned {
+                            Some(string_content) => string_content.len(),
+                            None => cursor.offset() - content_start,
+                        };
+                        if len > max_string_length {
+                            return Err(LexError::StringTooLong {
+                                limit: max_string_length,
+                                span: cursor.span_from(start),
+                            });
+                        }
+                        let escape_start = cursor.mark();
+                        let c = match cursor.next() {
+                            Some(c) => c,
+                            None => break,
+                        };
+
+                        if c == quote {
+                            content_end = escape_start.start;
+                            terminated = true;
+                            break;
+                        } else if c == '\\' {
+                            let string_content = owned.get_or_insert_with(|| {
+                                String::from_utf8_lossy(&input[content_start..escape_start.start])
+                                    .into_owned()
+                            });
+                            let escaped = match cursor.next() {
+                                Some('"') => Ok(Some('"')),
+                                Some('\\') => Ok(Some('\\')),
+                                Some('/') => Ok(Some('/')),
+                                Some('b') => Ok(Some('\u{8}')),
+                                Some('f') => Ok(Some('\u{c}')),
+                                Some('n') => Ok(Some('\n')),
+                                Some('r') => Ok(Some('\r')),
+                                Some('t') => Ok(Some('\t')),
+                                Some('u') => read_unicode_escape(cursor, escape_start).map(Some),
+                                Some(other) if json5 => {
+                                    read_json5_escape(cursor, other, escape_start)
+                                }
+                                Some(other) => {
+                                    let span = cursor.span_from(escape_start);
+                                    Err(LexError::InvalidEscape(other, span))
+                                }
+                                None => break,
+                            };
+                            match escaped {
+                                Ok(Some(c)) => string_content.push(c),
+                                Ok(None) => {}
+                                Err(e) => {
+                                    // leave the cursor after the string, not inside it
+                                    skip_string(cursor, quote);
+                                    return Err(e);
+                                }
+                            }
+                        } else if c < ' ' && (!json5 || matches!(c, '\n' | '\r')) {
+                            // JSON5 only forbids line breaks
+                            let span = cursor.span_from(escape_start);
+                            skip_string(cursor, quote);
+                            return Err(LexError::ControlCharacter(c, span));
+                        } else if let Some(string_content) = &mut owned {
+                            string_content.push(c);
+                        }
+                    }
+
+                    if !terminated {
+                        return Err(LexError::UnterminatedString(cursor.span_from(start)));
+                    }
+
+                    match owned {
+                        Some(string_content) => Token::String(Cow::Owned(string_content)),
+                        None => Token::String(String::from_utf8_lossy(
+                            &input[content_start..content_end],
+                        )),
+                    }
+                }
+
+                // a literal, `Infinity`/`NaN`, or an identifier key, which may
+                // spell chars as `\uXXXX` escapes
+                c if json5 && (is_identifier_start(c) || c == '\\') => {
+                    // stays `None`, and the name is borrowed from the input,
+                    // until the first escape
+                    let mut owned: Option<String> = None;
+                    let (mut c, mut char_start) = (c, start);
+                    loop {
+                        if c == '\\' {
+                            let decoded = match cursor.next() {
+                                Some('u') => read_unicode_escape(cursor, char_start)?,
+                                Some(other) => {
+                                    let span = cursor.span_from(char_start);
+                                    return Err(LexError::InvalidEscape(other, span));
+                                }
+                                None => {
+                                    return Err(LexError::InvalidToken(
+                                        '\\',
+                                        cursor.span_from(start),
+                                    ))
+                                }
+                            };
+                            let allowed = if char_start == start {
+                                is_identifier_start(decoded)
+                            } else {
+                                is_identifier_char(decoded)
+                            };
+                            if !allowed {
+                                return Err(LexError::InvalidUnicodeEscape(
+                                    cursor.span_from(char_start),
+                                ));
+                            }
+                            owned
+                                .get_or_insert_with(|| {
+                                    String::from_utf8_lossy(&input[start.start..char_start.start])
+                                        .into_owned()
+                                })
+                                .push(decoded);
+                        } else if let Some(name) = &mut owned {
+                            name.push(c);
+                        }
+                        match cursor.peek() {
+                            Some(next) if is_identifier_char(next) || next == '\\' => {
+                                char_start = cursor.mark();
+                                c = next;
+                                cursor.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    match owned {
+                        // an escaped name is never a keyword
+                        Some(name) => Token::Identifier(Cow::Owned(name)),
+                        None => {
+                            let ident =
+                                String::from_utf8_lossy(&input[start.start..cursor.offset()]);
+                            match ident.as_ref() {
+                                "true" => Token::True,
+                                "false" => Token::False,
+                                "null" => Token::Null,
+                                "Infinity" | "NaN" => Token::Number(ident),
+                                _ => Token::Identifier(ident),
+                            }
+                        }
+                    }
+                }
+
+                // could be a boolean literal, 'null', or invalid
+                c if c.is_alphabetic() => {
+                    let mut ident = c.to_string();
+                    while let Some(next_char) = cursor.peek() {
+                        if next_char.is_alphabetic() {
+                            ident.push(next_char);
+                            cursor.next(); // consume
+                        } else {
+                            break;
+                        }
+                    }
+                    match ident.as_str() {
+                        "true" => Token::True,
+                        "false" => Token::False,
+                        "null" => Token::Null,
+                        _ => return Err(LexError::InvalidToken(c, cursor.span_from(start))),
+                    }
+                }
+
+                // number (or minus sign + number)
+                c if c.is_ascii_digit() || c == '-' || (json5 && matches!(c, '+' | '.')) => {
+                    let scanned = if json5 {
+                        scan_json5_number(cursor, c)
+                    } else {
+                        scan_number(cursor, c)
+                    };
+                    match scanned {
+                        Some(()) => Token::Number(String::from_utf8_lossy(
+                            &input[start.start..cursor.offset()],
+                        )),
+                        None => {
+                            // swallow the rest of the malformed number so the error covers all of it
+                            while cursor.peek().is_some_and(is_number_char) {
+                                cursor.next();
+                            }
+                            return Err(LexError::InvalidNumber(cursor.span_from(start)));
+                        }
+                    }
+                }
+
+                // anything else is invalid
+                other => return Err(LexError::InvalidToken(other, cursor.span_from(start))),
+            };
+
+            return Ok(SpannedToken {
+                token,
+                span: cursor.span_from(start),
+            });
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<SpannedToken<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.next_token();
+        if matches!(&result, Ok(spanned) if spanned.token == Token::Eof) {
+            self.finished = true;
+        }
+        Some(result)
+    }
+}
+
+/// Lexes the whole input at once, ending with an `Eof` token.
+pub fn tokenize(input: &str) -> Result<Vec<SpannedToken<'_>>, LexError> {
+    Lexer::new(input).collect()
+}
+
+/// Reads the rest of a number whose first char (a digit or '-') has already
+/// been consumed, following the RFC 8259 grammar
+/// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`.
+/// Returns `None` if the input doesn't match, including when more number
+/// chars follow a complete number (`01`, `1.2.3`).
+fn scan_number(cursor: &mut Cursor<'_>, first: char) -> Option<()> {
+    // integer part: a lone '0' or digits without a leading zero
+    let leading = if first == '-' {
+        let digit = cursor.peek().filter(char::is_ascii_digit)?;
+        cursor.next();
+        digit
+    } else {
+        first
+    };
+    if leading != '0' {
+        skip_digits(cursor);
+    }
+
+    if cursor.peek() == Some('.') {
+        cursor.next();
+        if skip_digits(cursor) == 0 {
+            return None;
+        }
+    }
+
+    if let Some('e' | 'E') = cursor.peek() {
+        cursor.next();
+        if let Some('+' | '-') = cursor.peek() {
+            cursor.next();
+        }
+        if skip_digits(cursor) == 0 {
+            return None;
+        }
+    }
+
+    if cursor.peek().is_some_and(is_number_char) {
+        return None;
+    }
+    Some(())
+}
+
+/// Consumes a run of ASCII digits, returning how many there were.
+fn skip_digits(cursor: &mut Cursor<'_>) -> usize {
+    let mut count = 0;
+    while cursor.peek().is_some_and(|c| c.is_ascii_digit()) {
+        cursor.next();
+        count += 1;
+    }
+    count
+}
+
+fn is_number_char(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-')
+}
+
+/// Like `scan_number`, for the JSON5 grammar: a number may also have a '+'
+/// sign, be hexadecimal (`0x1F`), start or end with '.', or be a signed
+/// `Infinity` or `NaN` (unsigned ones are lexed as identifiers).
+fn scan_json5_number(cursor: &mut Cursor<'_>, first: char) -> Option<()> {
+    let first = if matches!(first, '+' | '-') {
+        if let Some('I' | 'N') = cursor.peek() {
+            let mut word = String::new();
+            while let Some(c) = cursor.peek().filter(|&c| is_identifier_char(c)) {
+                word.push(c);
+                cursor.next();
+            }
+            return matches!(word.as_str(), "Infinity" | "NaN").then_some(());
+        }
+        cursor.next()?
+    } else {
+        first
+    };
+
+    if first == '0' && matches!(cursor.peek(), Some('x' | 'X')) {
+        cursor.next();
+        let mut count = 0;
+        while cursor.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            cursor.next();
+            count += 1;
+        }
+        return (count > 0 && !cursor.peek().is_some_and(is_number_char)).then_some(());
+    }
+
+    // digits on at least one side of the '.', without a leading zero
+    let mut digits = match first {
+        '0' => 1,
+        '1'..='9' => 1 + skip_digits(cursor),
+        '.' => 0,
+        _ => return None,
+    };
+    if first == '.' || cursor.peek() == Some('.') {
+        if first != '.' {
+            cursor.next();
+        }
+        digits += skip_digits(cursor);
+    }
+    if digits == 0 {
+        return None;
+    }
+
+    if let Some('e' | 'E') = cursor.peek() {
+        cursor.next();
+        if let Some('+' | '-') = cursor.peek() {
+            cursor.next();
+        }
+        if skip_digits(cursor) == 0 {
+            return None;
+        }
+    }
+
+    if cursor.peek().is_some_and(is_number_char) {
+        return None;
+    }
+    Some(())
+}
+
+/// Whether `c` can start a JSON5 identifier key.
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '$' || c == '_'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '$' | '_' | '\u{200c}' | '\u{200d}')
+}
+
+/// Moves past the rest of a string that has a bad escape in it, up to and
+/// including the closing `quote` if there is one.
+fn skip_string(cursor: &mut Cursor<'_>, quote: char) {
+    while let Some(c) = cursor.next() {
+        if c == quote {
+            return;
+        }
+        if c == '\\' {
+            cursor.next();
+        }
+    }
+}
+
+/// Skips a JSON5/JSONC `//` or `/* */` comment whose leading '/' has already
+/// been consumed.
+fn skip_comment(cursor: &mut Cursor<'_>, start: Span) -> Result<(), LexError> {
+    match cursor.next() {
+        Some('/') => {
+            while cursor
+                .peek()
+                .is_some_and(|c| !matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}'))
+            {
+                cursor.next();
+            }
+            Ok(())
+        }
+        Some('*') => {
+            let mut star = false;
+            for c in cursor.by_ref() {
+                if star && c == '/' {
+                    return Ok(());
+                }
+                star = c == '*';
+            }
+            Err(LexError::UnterminatedComment(cursor.span_from(start)))
+        }
+        _ => Err(LexError::InvalidToken('/', cursor.span_from(start))),
+    }
+}
+
+/// Decodes the `XXXX` part of a `\uXXXX` escape (the `\u` is already consumed).
+/// A high surrogate must be followed by a `\uXXXX` low surrogate, and the pair
+/// is joined into a single `char`.
+fn read_unicode_escape(cursor: &mut Cursor<'_>, start: Span) -> Result<char, LexError> {
+    let high = match read_hex(cursor, 4) {
+        Some(high) => high,
+        None => return Err(LexError::InvalidUnicodeEscape(cursor.span_from(start))),
+    };
+    let code = match high {
+        0xD800..=0xDBFF => {
+            let low = match (cursor.next(), cursor.next()) {
+                (Some('\\'), Some('u')) => read_hex(cursor, 4),
+                _ => None,
+            };
+            match low {
+                Some(low @ 0xDC00..=0xDFFF) => 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                _ => return Err(LexError::InvalidUnicodeEscape(cursor.span_from(start))),
+            }
+        }
+        // a low surrogate without a high surrogate before it
+        0xDC00..=0xDFFF => return Err(LexError::InvalidUnicodeEscape(cursor.span_from(start))),
+        _ => high,
+    };
+
+    match char::from_u32(code) {
+        Some(c) => Ok(c),
+        None => Err(LexError::InvalidUnicodeEscape(cursor.span_from(start))),
+    }
+}
+
+fn read_hex(cursor: &mut Cursor<'_>, digits: usize) -> Option<u32> {
+    let mut value = 0;
+    for _ in 0..digits {
+        // a non-digit is left alone, it may be the string's closing quote
+        value = value * 16 + cursor.peek()?.to_digit(16)?;
+        cursor.next();
+    }
+    Some(value)
+}
+
+/// Decodes an escape that only JSON5 allows, whose char `c` after the '\\'
+/// has already been consumed. Returns `None` for an escaped line break, which
+/// continues the string on the next line.
+fn read_json5_escape(
+    cursor: &mut Cursor<'_>,
+    c: char,
+    start: Span,
+) -> Result<Option<char>, LexError> {
+    let escaped = match c {
+        'v' => '\u{b}',
+        '0' if !cursor.peek().is_some_and(|c| c.is_ascii_digit()) => '\0',
+        'x' => match read_hex(cursor, 2).and_then(char::from_u32) {
+            Some(c) => c,
+            None => return Err(LexError::InvalidEscape('x', cursor.span_from(start))),
+        },
+        '\r' => {
+            if cursor.peek() == Some('\n') {
+                cursor.next();
+            }
+            return Ok(None);
+        }
+        '\n' | '\u{2028}' | '\u{2029}' => return Ok(None),
+        '0'..='9' => return Err(LexError::InvalidEscape(c, cursor.span_from(start))),
+        // any other char, such as '\'', stands for itself
+        c => c,
+    };
+    Ok(Some(escaped))
+}
+
+/// Which of the `ParserOptions` size limits a document went over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    InputSize,     // `max_input_bytes`
+    StringLength,  // `max_string_length`
+    ArrayLength,   // `max_array_length`
+    ObjectMembers, // `max_object_members`
+    Nodes,         // `max_nodes`
+}
+
+/// What the parser was looking for when it hit an unexpected token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    Value,
+    Key,
+    KeyOrRBrace,
+    Colon,
+    CommaOrRBrace,
+    CommaOrRBracket,
+    Eof,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Value => write!(f, "a value"),
+            Expected::Key => write!(f, "a string key"),
+            Expected::KeyOrRBrace => write!(f, "a string key or '}}'"),
+            Expected::Colon => write!(f, "':' after object key"),
+            Expected::CommaOrRBrace => write!(f, "',' or '}}' after object member"),
+            Expected::CommaOrRBracket => write!(f, "',' or ']' after array element"),
+            Expected::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    UnexpectedEndOfTokens(Expected, Span),
+    UnexpectedToken(Token<'static>, Expected, Span),
+    DuplicateKey {
+        key: String,
+        first: Span,
+        second: Span,
+    },
+    /// An array or object opened more than `ParserOptions::max_depth` levels
+    /// deep. `span` is its '[' or '{'.
+    DepthLimitExceeded {
+        limit: usize,
+        span: Span,
+    },
+    /// The document is bigger than one of the `ParserOptions` size limits
+    /// allow. `position` is the token that went over it.
+    LimitExceeded {
+        kind: LimitKind,
+        limit: usize,
+        position: Span,
+    },
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedEndOfTokens(_, span)
+            | ParseError::UnexpectedToken(_, _, span)
+            | ParseError::DuplicateKey { second: span, .. }
+            | ParseError::DepthLimitExceeded { span, .. }
+            | ParseError::LimitExceeded { position: span, .. } => *span,
+        }
+    }
+
+    /// The error text without its position.
+    pub fn message(&self) -> String {
+        match self {
+            ParseError::UnexpectedEndOfTokens(expected, _) => {
+                format!("expected {}, found end of input", expected)
+            }
+            ParseError::UnexpectedToken(token, expected, _) => {
+                format!("expected {}, found {}", expected, token)
+            }
+            ParseError::DuplicateKey { key, first, .. } => {
+                format!("duplicate key {:?}, first defined at {}", key, first)
+            }
+            ParseError::DepthLimitExceeded { limit, .. } => {
+                format!("arrays and objects nested more than {} deep", limit)
+            }
+            ParseError::LimitExceeded { kind, limit, .. } => match kind {
+                LimitKind::InputSize => format!("input longer than {} bytes", limit),
+                LimitKind::StringLength => format!("string longer than {} bytes", limit),
+                LimitKind::ArrayLength => format!("array with more than {} elements", limit),
+                LimitKind::ObjectMembers => format!("object with more than {} members", limit),
+                LimitKind::Nodes => format!("document with more than {} values", limit),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message(), self.span())
+    }
+}
+
+impl Error for ParseError {}
+
+/// Any error `parse_json_str` can produce.
+#[derive(Debug)]
+pub enum JsonError {
+    Lex(LexError),
+    Parse(ParseError),
+}
+
+impl JsonError {
+    pub fn span(&self) -> Span {
+        match self {
+            JsonError::Lex(e) => e.span(),
+            JsonError::Parse(e) => e.span(),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            JsonError::Lex(e) => e.message(),
+            JsonError::Parse(e) => e.message(),
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Lex(e) => e.fmt(f),
+            JsonError::Parse(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for JsonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JsonError::Lex(e) => Some(e),
+            JsonError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<LexError> for JsonError {
+    fn from(e: LexError) -> Self {
+        JsonError::Lex(e)
+    }
+}
+
+impl From<ParseError> for JsonError {
+    fn from(e: ParseError) -> Self {
+        JsonError::Parse(e)
+    }
+}
+
+
+/// What `Parser` does when an object repeats a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeyPolicy {
+    /// Fail with `ParseError::DuplicateKey`.
+    Error,
+    /// Keep the first value and ignore the later ones.
+    FirstWins,
+    /// Keep the last value, in the position of the first.
+    #[default]
+    LastWins,
+    /// Keep every pair, see `Map::append` and `Map::get_all`. Objects always
+    /// use `MapBackend::Ordered` here, as the other backends can't hold
+    /// duplicate keys.
+    CollectAll,
+}
+
+/// Which syntax `Lexer` and `Parser` accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// RFC 8259 JSON and nothing else.
+    #[default]
+    Strict,
+    /// JSON with `//` and `/* */` comments, as in VS Code's `settings.json`.
+    Jsonc,
+    /// JSON5 (<https://spec.json5.org>): comments, trailing commas,
+    /// identifier keys, single-quoted strings with extra escapes, hex numbers,
+    /// `Infinity`/`NaN`, a leading '+', and numbers starting or ending with '.'.
+    Json5,
+}
+
+/// Settings that change how `Parser` builds values.
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    /// Keep each number's original spelling instead of converting it, so
+    /// values beyond `i64`/`u64`/`f64` precision round-trip exactly.
+    pub arbitrary_precision: bool,
+    /// How objects store their members. Defaults to keeping source order.
+    pub map_backend: MapBackend,
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// For byte input, read invalid UTF-8 as U+FFFD instead of failing with
+    /// `LexError::InvalidUtf8`.
+    pub lossy_utf8: bool,
+    pub dialect: Dialect,
+    /// How deeply arrays and objects may nest before parsing fails with
+    /// `ParseError::DepthLimitExceeded`. Defaults to 128.
+    ///
+    /// `Parser` and `JsonReader` keep open containers on the heap, and values
+    /// are dropped and made owned without recursion, so `usize::MAX` is safe
+    /// for them. `cst::Document`, `recovery::parse_recovering`, `Clone`,
+    /// `PartialEq` and the serializer still recurse once per level and need a
+    /// limit that fits the stack.
+    pub max_depth: usize,
+    /// The longest input, in bytes. `Parser::parse_json` (and so
+    /// `parse_json_str` and `parse_bytes`) and `cst::Document` check the
+    /// length of the whole input before reading any of it. `StreamingParser`,
+    /// `from_reader` and `NdjsonReader` apply it to each value or line, which
+    /// bounds how much they buffer.
+    ///
+    /// This and the limits below fail with `ParseError::LimitExceeded` as
+    /// soon as they are crossed, and all default to `usize::MAX`, i.e. none.
+    pub max_input_bytes: usize,
+    /// The longest string or object key, in bytes once escapes are decoded.
+    /// The lexer gives up on a string as soon as it gets this long, so no
+    /// more than this is ever decoded.
+    pub max_string_length: usize,
+    pub max_array_length: usize,
+    /// The most members in one object, counting any duplicate keys.
+    pub max_object_members: usize,
+    /// The most values in one document, counting every array, object and
+    /// scalar at any depth.
+    pub max_nodes: usize,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            arbitrary_precision: false,
+            map_backend: MapBackend::default(),
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            lossy_utf8: false,
+            dialect: Dialect::default(),
+            max_depth: 128,
+            max_input_bytes: usize::MAX,
+            max_string_length: usize::MAX,
+            max_array_length: usize::MAX,
+            max_object_members: usize::MAX,
+            max_nodes: usize::MAX,
+        }
+    }
+}
+
+/// Parser that pulls tokens from a `Lexer` as it goes, so only the token
+/// under the cursor is held in memory. It doesn't recurse: the arrays and
+/// objects open at the cursor are kept on a stack of their own.
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<SpannedToken<'a>>,
+    consumed: usize, // byte offset just past the last token consumed
+    depth: usize,    // arrays and objects open at the cursor
+    nodes: usize,    // values started in the current top-level value
+    options: ParserOptions,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Parser::with_options(input, ParserOptions::default())
+    }
+
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Self {
+        Parser::from_bytes_with_options(input.as_bytes(), options)
+    }
+
+    /// A parser for input that may not be valid UTF-8, see `Lexer::from_bytes`.
+    pub fn from_bytes(input: &'a [u8]) -> Self {
+        Parser::from_bytes_with_options(input, ParserOptions::default())
+    }
+
+    pub fn from_bytes_with_options(input: &'a [u8], options: ParserOptions) -> Self {
+        Parser {
+            lexer: Lexer::from_bytes_with_options(input, &options),
+            peeked: None,
+            consumed: 0,
+            depth: 0,
+            nodes: 0,
+            options,
+        }
+    }
+
+    /// The token under the cursor, lexed on first use and kept until `advance`.
+    fn peek(&mut self) -> Result<&mut SpannedToken<'a>, JsonError> {
+        let spanned = match self.peeked.take() {
+            Some(spanned) => spanned,
+            None => {
+                let spanned = match self.lexer.next_token() {
+                    Ok(spanned) => spanned,
+                    Err(LexError::StringTooLong { span, .. }) => {
+                        return Err(self.limit_exceeded(LimitKind::StringLength, span))
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                self.check_token(&spanned)?;
+                spanned
+            }
+        };
+        Ok(self.peeked.insert(spanned))
+    }
+
+    /// Fails straight away if the whole input is over `max_input_bytes`, for
+    /// the entry points that parse all of it. The error covers the input.
+    fn check_input_size(&self) -> Result<(), JsonError> {
+        let len = self.lexer.cursor.input.len();
+        if len > self.options.max_input_bytes {
+            let span = Span {
+                start: 0,
+                end: len,
+                line: 1,
+                column: 1,
+            };
+            return Err(self.limit_exceeded(LimitKind::InputSize, span));
+        }
+        Ok(())
+    }
+
+    /// Applies the limits that a single token can go over.
+    fn check_token(&self, spanned: &SpannedToken<'a>) -> Result<(), JsonError> {
+        if spanned.span.end > self.options.max_input_bytes {
+            return Err(self.limit_exceeded(LimitKind::InputSize, spanned.span));
+        }
+        if let Token::String(s) | Token::Identifier(s) = &spanned.token {
+            if s.len() > self.options.max_string_length {
+                return Err(self.limit_exceeded(LimitKind::StringLength, spanned.span));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if a container already holding `len` items of `kind` is about to
+    /// get another one, at the cursor.
+    fn check_len(&mut self, kind: LimitKind, len: usize) -> Result<(), JsonError> {
+        let limit = match kind {
+            LimitKind::ArrayLength => self.options.max_array_length,
+            _ => self.options.max_object_members,
+        };
+        if len >= limit {
+            let position = self.current_span()?;
+            return Err(self.limit_exceeded(kind, position));
+        }
+        Ok(())
+    }
+
+    /// Counts the value at the cursor towards `max_nodes`. Every top-level
+    /// value starts the count again.
+    fn count_node(&mut self) -> Result<(), JsonError> {
+        if self.depth == 0 {
+            self.nodes = 0;
+        }
+        if self.nodes >= self.options.max_nodes {
+            let position = self.current_span()?;
+            return Err(self.limit_exceeded(LimitKind::Nodes, position));
+        }
+        self.nodes += 1;
+        Ok(())
+    }
+
+    fn limit_exceeded(&self, kind: LimitKind, position: Span) -> JsonError {
+        let limit = match kind {
+            LimitKind::InputSize => self.options.max_input_bytes,
+            LimitKind::StringLength => self.options.max_string_length,
+            LimitKind::ArrayLength => self.options.max_array_length,
+            LimitKind::ObjectMembers => self.options.max_object_members,
+            LimitKind::Nodes => self.options.max_nodes,
+        };
+        ParseError::LimitExceeded {
+            kind,
+            limit,
+            position,
+        }
+        .into()
+    }
+
+    fn current_token(&mut self) -> Result<Option<&Token<'a>>, JsonError> {
+        let spanned = self.peek()?;
+        if spanned.token == Token::Eof {
+            return Ok(None);
+        }
+        Ok(Some(&spanned.token))
+    }
+
+    fn current_span(&mut self) -> Result<Span, JsonError> {
+        Ok(self.peek()?.span)
+    }
+
+    /// The error for a token the grammar does not allow at this point.
+    fn unexpected(&mut self, expected: Expected) -> JsonError {
+        let spanned = match self.peek() {
+            Ok(spanned) => spanned,
+            Err(e) => return e,
+        };
+        let error = match &spanned.token {
+            Token::Eof => ParseError::UnexpectedEndOfTokens(expected, spanned.span),
+            token => {
+                ParseError::UnexpectedToken(token.clone().into_owned(), expected, spanned.span)
+            }
+        };
+        error.into()
+    }
+
+    fn advance(&mut self) {
+        if let Some(spanned) = self.peeked.take() {
+            self.consumed = spanned.span.end;
+        }
+    }
+
+    /// Goes one level deeper for the '[' or '{' under the cursor, unless that
+    /// would be deeper than `max_depth`. The caller lowers `depth` again once
+    /// the container is closed.
+    fn enter(&mut self) -> Result<(), JsonError> {
+        if self.depth >= self.options.max_depth {
+            return Err(ParseError::DepthLimitExceeded {
+                limit: self.options.max_depth,
+                span: self.current_span()?,
+            }
+            .into());
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// If the current token is a string, moves it out and advances past it.
+    fn take_string(&mut self) -> Result<Option<Cow<'a, str>>, JsonError> {
+        let s = match &mut self.peek()?.token {
+            Token::String(s) => mem::take(s),
+            _ => return Ok(None),
+        };
+        self.advance();
+        Ok(Some(s))
+    }
+
+    /// Like `take_string`, but also takes a JSON5 identifier key, which may
+    /// be a reserved word such as `null` or `Infinity`.
+    fn take_key(&mut self) -> Result<Option<Cow<'a, str>>, JsonError> {
+        let json5 = self.options.dialect == Dialect::Json5;
+        let key = match &mut self.peek()?.token {
+            Token::String(s) | Token::Identifier(s) => mem::take(s),
+            Token::True if json5 => Cow::Borrowed("true"),
+            Token::False if json5 => Cow::Borrowed("false"),
+            Token::Null if json5 => Cow::Borrowed("null"),
+            Token::Number(s) if json5 && matches!(s.as_ref(), "Infinity" | "NaN") => mem::take(s),
+            _ => return Ok(None),
+        };
+        self.advance();
+        Ok(Some(key))
+    }
+
+    /// Parses the whole input as one value. Input over `max_input_bytes` is
+    /// rejected before any of it is read.
+    pub fn parse_json(&mut self) -> Result<JsonValue<'a>, JsonError> {
+        self.parse_json_with(borrowed)
+    }
+
+    /// Like `parse_json`, but copies strings and keys out of the input as it
+    /// goes, rather than building a borrowed value and copying that.
+    fn parse_json_owned(&mut self) -> Result<JsonValue<'static>, JsonError> {
+        self.parse_json_with(owned)
+    }
+
+    fn parse_json_with<'o>(&mut self, own: Own<'a, 'o>) -> Result<JsonValue<'o>, JsonError> {
+        self.check_input_size()?;
+        let value = self.parse_value_with(own)?;
+        // should be at end after one top-level value
+        if self.current_token()?.is_some() {
+            return Err(self.unexpected(Expected::Eof));
+        }
+        Ok(value)
+    }
+
+    /// Parses the value under the cursor, however deeply nested. Arrays and
+    /// objects that are still open are kept on a heap-allocated stack rather
+    /// than the call stack, so only `max_depth` and memory limit the nesting.
+    fn parse_value(&mut self) -> Result<JsonValue<'a>, JsonError> {
+        self.parse_value_with(borrowed)
+    }
+
+    /// Like `parse_value`, with `own` turning each string and key from the
+    /// input into what the value keeps.
+    fn parse_value_with<'o>(&mut self, own: Own<'a, 'o>) -> Result<JsonValue<'o>, JsonError> {
+        let mut stack = Vec::new();
+        loop {
+            let mut value = match self.start_value(&mut stack, own)? {
+                Some(value) => value,
+                None => continue, // opened a container, its first item is next
+            };
+            // hand the finished value to the innermost open container, and
+            // close each container that ends with it
+            loop {
+                let frame = match stack.last_mut() {
+                    Some(frame) => frame,
+                    None => return Ok(value),
+                };
+                if !self.end_item(frame, value, own)? {
+                    break;
+                }
+                value = match stack.pop() {
+                    Some(Frame::Array(arr)) => JsonValue::Array(arr),
+                    Some(Frame::Object { map, .. }) => JsonValue::Object(map),
+                    None => unreachable!("a frame was just ended"),
+                };
+                self.depth -= 1;
+            }
+        }
+    }
+
+    /// Starts the value under the cursor. A scalar, `[]` or `{}` is parsed
+    /// whole and returned. Any other array or object is pushed onto `stack`,
+    /// leaving the cursor at its first element or at its first member's value.
+    fn start_value<'o>(
+        &mut self,
+        stack: &mut Vec<Frame<'a, 'o>>,
+        own: Own<'a, 'o>,
+    ) -> Result<Option<JsonValue<'o>>, JsonError> {
+        self.count_node()?;
+        if let Some(s) = self.take_string()? {
+            return Ok(Some(JsonValue::String(own(s))));
+        }
+
+        let arbitrary_precision = self.options.arbitrary_precision;
+        let token = match self.current_token()? {
+            Some(token) => token,
+            None => return Err(self.unexpected(Expected::Value)),
+        };
+
+        let value = match token {
+            Token::LBrace => {
+                self.enter()?;
+                self.advance(); // consume '{'
+                let map = match self.options.duplicate_keys {
+                    DuplicateKeyPolicy::CollectAll => Map::with_backend(MapBackend::Ordered),
+                    _ => Map::with_backend(self.options.map_backend),
+                };
+
+                // if next is '}', it's an empty object
+                if let Some(Token::RBrace) = self.current_token()? {
+                    self.advance(); // consume '}'
+                    self.depth -= 1;
+                    return Ok(Some(JsonValue::Object(map)));
+                }
+
+                self.check_len(LimitKind::ObjectMembers, 0)?;
+                let mut key_spans = HashMap::new();
+                let key = self.parse_key(&mut key_spans, Expected::KeyOrRBrace)?;
+                stack.push(Frame::Object {
+                    map,
+                    len: 1,
+                    key: own(key),
+                    key_spans,
+                });
+                return Ok(None);
+            }
+            Token::LBracket => {
+                self.enter()?;
+                self.advance(); // consume '['
+
+                // if next is ']', empty array
+                if let Some(Token::RBracket) = self.current_token()? {
+                    self.advance(); // consume ']'
+                    self.depth -= 1;
+                    return Ok(Some(JsonValue::Array(Vec::new())));
+                }
+
+                self.check_len(LimitKind::ArrayLength, 0)?;
+                stack.push(Frame::Array(Vec::new()));
+                return Ok(None);
+            }
+            Token::Number(num_str) => {
+                // the lexer only produces numbers `from_token` understands, but
+                // fail cleanly rather than panic if that ever stops being true
+                match JsonNumber::from_token(num_str, arbitrary_precision) {
+                    Some(number) => JsonValue::Number(number),
+                    None => return Err(self.unexpected(Expected::Value)),
+                }
+            }
+            Token::True => JsonValue::Bool(true),
+            Token::False => JsonValue::Bool(false),
+            Token::Null => JsonValue::Null,
+            _ => return Err(self.unexpected(Expected::Value)),
+        };
+        self.advance();
+        Ok(Some(value))
+    }
+
+    /// Expects an object key and the ':' after it.
+    fn parse_key(
+        &mut self,
+        key_spans: &mut HashMap<Cow<'a, str>, Span>,
+        expected: Expected,
+    ) -> Result<Cow<'a, str>, JsonError> {
+        let key_span = self.current_span()?;
+        let key = match self.take_key()? {
+            Some(key) => key,
+            None => return Err(self.unexpected(expected)),
+        };
+        if self.options.duplicate_keys == DuplicateKeyPolicy::Error {
+            if let Some(&first) = key_spans.get(&key) {
+                return Err(ParseError::DuplicateKey {
+                    key: key.into_owned(),
+                    first,
+                    second: key_span,
+                }
+                .into());
+            }
+            key_spans.insert(key.clone(), key_span);
+        }
+
+        match self.current_token()? {
+            Some(Token::Colon) => self.advance(),
+            _ => return Err(self.unexpected(Expected::Colon)),
+        }
+        Ok(key)
+    }
+
+    /// Adds a finished `value` to the container `frame` and moves past the
+    /// ',' or closing bracket after it. Returns `true` if the container is
+    /// now closed; otherwise the cursor is at the start of its next value.
+    fn end_item<'o>(
+        &mut self,
+        frame: &mut Frame<'a, 'o>,
+        value: JsonValue<'o>,
+        own: Own<'a, 'o>,
+    ) -> Result<bool, JsonError> {
+        let trailing_commas = self.options.dialect == Dialect::Json5;
+        match frame {
+            Frame::Array(arr) => {
+                arr.push(value);
+                match self.current_token()? {
+                    Some(Token::Comma) => {
+                        self.advance(); // consume ','
+
+                        // JSON5 allows a trailing comma
+                        if trailing_commas {
+                            if let Some(Token::RBracket) = self.current_token()? {
+                                self.advance(); // consume ']'
+                                return Ok(true);
+                            }
+                        }
+                        self.check_len(LimitKind::ArrayLength, arr.len())?;
+                        Ok(false)
+                    }
+                    Some(Token::RBracket) => {
+                        self.advance(); // consume ']'
+                        Ok(true)
+                    }
+                    _ => Err(self.unexpected(Expected::CommaOrRBracket)),
+                }
+            }
+            Frame::Object {
+                map,
+                len,
+                key: next_key,
+                key_spans,
+            } => {
+                let key = mem::take(next_key);
+                match self.options.duplicate_keys {
+                    DuplicateKeyPolicy::FirstWins => {
+                        if !map.contains_key(&key) {
+                            map.insert(key, value);
+                        }
+                    }
+                    DuplicateKeyPolicy::CollectAll => map.append(key, value),
+                    DuplicateKeyPolicy::Error | DuplicateKeyPolicy::LastWins => {
+                        map.insert(key, value);
+                    }
+                }
+
+                match self.current_token()? {
+                    Some(Token::Comma) => {
+                        self.advance(); // consume ','
+
+                        // JSON5 allows a trailing comma
+                        if trailing_commas {
+                            if let Some(Token::RBrace) = self.current_token()? {
+                                self.advance(); // consume '}'
+                                return Ok(true);
+                            }
+                        }
+                        self.check_len(LimitKind::ObjectMembers, *len)?;
+                        *next_key = own(self.parse_key(key_spans, Expected::Key)?);
+                        *len += 1;
+                        Ok(false)
+                    }
+                    Some(Token::RBrace) => {
+                        self.advance(); // consume '}'
+                        Ok(true)
+                    }
+                    _ => Err(self.unexpected(Expected::CommaOrRBrace)),
+                }
+            }
+        }
+    }
+}
+
+/// An array or object `Parser::parse_value` has opened but not yet closed,
+/// for input borrowed for `'a` and a value that keeps strings for `'o`.
+enum Frame<'a, 'o> {
+    Array(Vec<JsonValue<'o>>),
+    Object {
+        map: Map<'o>,
+        len: usize,        // members so far, which `map` doesn't count if keys repeat
+        key: Cow<'o, str>, // the key of the member whose value comes next
+        // where each key was first seen, only needed to report duplicates
+        key_spans: HashMap<Cow<'a, str>, Span>,
+    },
+}
+
+/// How `Parser::parse_value_with` keeps the strings and keys it takes from
+/// the input: borrowed where they have no escapes, or always copied.
+type Own<'a, 'o> = fn(Cow<'a, str>) -> Cow<'o, str>;
+
+fn borrowed(s: Cow<'_, str>) -> Cow<'_, str> {
+    s
+}
+
+fn owned(s: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(s.into_owned())
+}
+
+/// Parses top-level values one after another until the input runs out, for
+/// documents that are several values back to back (`{"a":1}{"b":2}[3]`),
+/// with or without whitespace between them. See `parse_many`.
+///
+/// Iteration stops after the first error, since there is no telling where
+/// the next value would start.
+pub struct ParseMany<'a> {
+    parser: Parser<'a>,
+    offset: usize,
+    finished: bool,
+}
+
+impl ParseMany<'_> {
+    /// The byte offset just past the last value returned, i.e. where the
+    /// unparsed rest of the input starts.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> Iterator for ParseMany<'a> {
+    type Item = Result<JsonValue<'a>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = match self.parser.current_token() {
+            Ok(None) => {
+                self.finished = true;
+                return None;
+            }
+            Ok(Some(_)) => self.parser.parse_value(),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(_) => self.offset = self.parser.consumed,
+            Err(_) => self.finished = true,
+        }
+        Some(result)
+    }
+}
+
+// -------------------------
+
+pub fn parse_json_str(input: &str) -> Result<JsonValue<'static>, JsonError> {
+    parse_json_str_with_options(input, ParserOptions::default())
+}
+
+pub fn parse_json_str_with_options(
+    input: &str,
+    options: ParserOptions,
+) -> Result<JsonValue<'static>, JsonError> {
+    Parser::with_options(input, options).parse_json_owned()
+}
+
+/// Parses without copying: strings and keys that contain no escapes borrow
+/// from `input`, and only escaped ones are allocated.
+pub fn parse_json_borrowed(input: &str) -> Result<JsonValue<'_>, JsonError> {
+    parse_json_borrowed_with_options(input, ParserOptions::default())
+}
+
+pub fn parse_json_borrowed_with_options(
+    input: &str,
+    options: ParserOptions,
+) -> Result<JsonValue<'_>, JsonError> {
+    Parser::with_options(input, options).parse_json()
+}
+
+pub fn parse_many(input: &str) -> ParseMany<'_> {
+    parse_many_with_options(input, ParserOptions::default())
+}
+
+pub fn parse_many_with_options(input: &str, options: ParserOptions) -> ParseMany<'_> {
+    ParseMany {
+        parser: Parser::with_options(input, options),
+        offset: 0,
+        finished: false,
+    }
+}
+
+/// Parses raw bytes, checking that they are UTF-8 as it goes. A leading byte
+/// order mark is skipped, and error spans are byte offsets into `input`.
+pub fn parse_bytes(input: &[u8]) -> Result<JsonValue<'static>, JsonError> {
+    parse_bytes_with_options(input, ParserOptions::default())
+}
+
+pub fn parse_bytes_with_options(
+    input: &[u8],
+    options: ParserOptions,
+) -> Result<JsonValue<'static>, JsonError> {
+    Parser::from_bytes_with_options(input, options).parse_json_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited(f: impl FnOnce(&mut ParserOptions)) -> ParserOptions {
+        let mut options = ParserOptions::default();
+        f(&mut options);
+        options
+    }
+
+    /// The kind and position of a `LimitExceeded` error.
+    fn limit_error<T: fmt::Debug>(result: Result<T, JsonError>) -> (LimitKind, Span) {
+        match result {
+            Err(JsonError::Parse(ParseError::LimitExceeded { kind, position, .. })) => {
+                (kind, position)
+            }
+            other => panic!("expected a limit error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn long_input_is_rejected_before_reading() {
+        let options = limited(|o| o.max_input_bytes = 10);
+        let input = format!("[{}", "1,".repeat(100));
+        let (kind, span) = limit_error(parse_json_str_with_options(&input, options.clone()));
+        assert_eq!(kind, LimitKind::InputSize);
+        assert_eq!((span.start, span.end), (0, input.len()));
+
+        let bytes = limit_error(parse_bytes_with_options(input.as_bytes(), options.clone()));
+        assert_eq!(bytes.0, LimitKind::InputSize);
+        let document = cst::Document::parse_with_options(&input, options.clone());
+        assert_eq!(limit_error(document).0, LimitKind::InputSize);
+
+        assert!(parse_json_str_with_options("[1, 2, 3]", options).is_ok());
+    }
+
+    #[test]
+    fn long_string_stops_at_the_limit() {
+        let options = limited(|o| o.max_string_length = 100);
+        for content in ["a".repeat(100_000), "\\u00e9".repeat(100_000)] {
+            let input = format!("[\"{}\"]", content);
+            let (kind, span) = limit_error(parse_json_str_with_options(&input, options.clone()));
+            assert_eq!(kind, LimitKind::StringLength);
+            assert_eq!(span.start, 1);
+            // at most 6 bytes of input per decoded byte
+            assert!(span.end < 700, "read up to {}", span.end);
+        }
+
+        let exact = format!("\"{}\"", "a".repeat(100));
+        assert!(parse_json_str_with_options(&exact, options.clone()).is_ok());
+        let over = format!("\"{}\"", "a".repeat(101));
+        assert!(parse_json_str_with_options(&over, options).is_err());
+    }
+
+    #[test]
+    fn count_limits() {
+        let options = limited(|o| o.max_array_length = 2);
+        assert!(parse_json_str_with_options("[1, 2]", options.clone()).is_ok());
+        let (kind, span) = limit_error(parse_json_str_with_options("[1, 2, 3]", options));
+        assert_eq!((kind, span.start), (LimitKind::ArrayLength, 7));
+
+        let options = limited(|o| o.max_object_members = 1);
+        let result = parse_json_str_with_options(r#"{"a": 1, "b": 2}"#, options);
+        assert_eq!(limit_error(result).0, LimitKind::ObjectMembers);
+
+        let options = limited(|o| o.max_nodes = 3);
+        assert!(parse_json_str_with_options("[1, [2]]", options.clone()).is_err());
+        assert!(parse_json_str_with_options("[1, 2]", options).is_ok());
+    }
+
+    #[test]
+    fn strings_borrow_from_the_input() {
+        fn is_borrowed(value: &JsonValue<'_>) -> bool {
+            matches!(value, JsonValue::String(Cow::Borrowed(_)))
+        }
+        fn into_members<'a>(mut value: JsonValue<'a>) -> Vec<(Cow<'a, str>, JsonValue<'a>)> {
+            match &mut value {
+                JsonValue::Object(map) => mem::take(map).into_iter().collect(),
+                other => panic!("expected an object, got {:?}", other),
+            }
+        }
+        fn as_array<'v, 'a>(value: &'v JsonValue<'a>) -> &'v [JsonValue<'a>] {
+            match value {
+                JsonValue::Array(items) => items,
+                other => panic!("expected an array, got {:?}", other),
+            }
+        }
+
+        let input = r#"{"plain": "text", "esc\u0061ped": "a\nb", "list": ["x", "\"y\""]}"#;
+        let value = parse_json_borrowed(input).unwrap();
+        let members = into_members(value.clone());
+        assert!(matches!(members[0].0, Cow::Borrowed("plain")));
+        assert!(is_borrowed(&members[0].1));
+        assert!(matches!(&members[1].0, Cow::Owned(key) if key == "escaped"));
+        assert!(!is_borrowed(&members[1].1));
+        assert_eq!(members[1].1, JsonValue::String("a\nb".into()));
+        let items = as_array(&members[2].1);
+        assert!(is_borrowed(&items[0]));
+        assert!(!is_borrowed(&items[1]));
+
+        // an owned parse gives the same value with everything copied
+        let owned = parse_json_str(input).unwrap();
+        assert_eq!(owned, value);
+        let members = into_members(owned);
+        assert!(matches!(members[0].0, Cow::Owned(_)));
+        assert!(!is_borrowed(&members[0].1));
+        assert!(!is_borrowed(&as_array(&members[2].1)[0]));
+    }
+
+    #[test]
+    fn number_grammar() {
+        for input in ["0", "-0", "10", "-0.5", "1E+2", "1e-2", "2.5E3"] {
+            let tokens = tokenize(input).unwrap();
+            assert_eq!(tokens[0].token, Token::Number(input.into()));
+            assert_eq!(tokens[1].token, Token::Eof);
+        }
+
+        for (input, start, end) in [
+            ("01", 0, 2),
+            ("1.", 0, 2),
+            ("-", 0, 1),
+            ("--1", 0, 3),
+            ("1.2.3", 0, 5),
+            ("1e", 0, 2),
+            ("1e+", 0, 3),
+            ("[1, -01]", 4, 7),
+        ] {
+            match tokenize(input) {
+                Err(LexError::InvalidNumber(span)) => {
+                    assert_eq!((span.start, span.end), (start, end), "{}", input);
+                }
+                other => panic!("{:?} gave {:?}", input, other),
+            }
+        }
+    }
+
+    /// Every value `parse_many` returns, written back out, with the byte
+    /// offset after each.
+    fn parse_all(input: &str) -> Vec<(Result<String, JsonError>, usize)> {
+        let mut values = parse_many(input);
+        let mut out = Vec::new();
+        while let Some(value) = values.next() {
+            out.push((value.map(|v| v.to_string()), values.byte_offset()));
+        }
+        out
+    }
+
+    #[test]
+    fn parse_many_values() {
+        let values = parse_all(r#"{"a":1}{"b":2}[3]"#);
+        let offsets: Vec<usize> = values.iter().map(|(_, offset)| *offset).collect();
+        assert_eq!(offsets, [7, 14, 17]);
+        assert_eq!(values[1].0.as_deref().unwrap(), r#"{"b":2}"#);
+
+        let values = parse_all(" 1 \"a\"\n\t[]  ");
+        let values: Vec<_> = values.into_iter().map(|(v, o)| (v.unwrap(), o)).collect();
+        assert_eq!(
+            values,
+            [
+                ("1".to_string(), 2),
+                ("\"a\"".to_string(), 6),
+                ("[]".to_string(), 10)
+            ]
+        );
+        assert!(parse_all(" \n ").is_empty());
+
+        // nothing more after an error, and the offset stays after the last value
+        let values = parse_all("[1] [2 3] 4");
+        assert_eq!(values.len(), 2);
+        assert!(values[0].0.is_ok());
+        let (error, offset) = &values[1];
+        assert_eq!(error.as_ref().unwrap_err().span().start, 7);
+        assert_eq!(*offset, 3);
+    }
+
+    #[test]
+    fn control_characters_in_strings() {
+        for input in ["\"a\nb\"", "\"\t\"", "[\"\u{0}\", 1]", "{\"a\u{1f}\": 1}"] {
+            match parse_json_str(input) {
+                Err(JsonError::Lex(LexError::ControlCharacter(..))) => {}
+                other => panic!("{:?} gave {:?}", input, other),
+            }
+        }
+        assert_eq!(
+            parse_json_str(r#""a\nb\t""#).unwrap(),
+            JsonValue::String("a\nb\t".into())
+        );
+        let error = parse_bytes(b"[\"x\ny\"]").unwrap_err();
+        assert_eq!(error.span().start, 3);
+        assert_eq!(
+            error.to_string(),
+            "unescaped control character U+000A in string at 1:4"
+        );
+
+        // JSON5 strings may hold any control character but a line break
+        assert_eq!(json5("'a\tb'").unwrap(), JsonValue::String("a\tb".into()));
+        assert!(json5("'a\nb'").is_err());
+        assert!(
+            parse_json_str_with_options("\"a\tb\"", limited(|o| o.dialect = Dialect::Jsonc))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        assert_eq!(
+            parse_json_str(r#""\ud83d\ude00 \uD83D\uDE00""#).unwrap(),
+            JsonValue::String("\u{1f600} \u{1f600}".into())
+        );
+        for input in [
+            r#""\ud83d""#,
+            r#""\ud83d x""#,
+            r#""\ud83d\u0041""#,
+            r#""\ude00""#,
+            r#""\ud83d\n""#,
+        ] {
+            match parse_json_str(input) {
+                Err(JsonError::Lex(LexError::InvalidUnicodeEscape(_))) => {}
+                other => panic!("{:?} gave {:?}", input, other),
+            }
+        }
+    }
+
+    fn json5(input: &str) -> Result<JsonValue<'static>, JsonError> {
+        parse_json_str_with_options(input, limited(|o| o.dialect = Dialect::Json5))
+    }
+    /// The keys of the JSON5 object `input`, in order.
+    fn json5_keys(input: &str) -> Vec<String> {
+        match &json5(input).unwrap() {
+            JsonValue::Object(object) => object.keys().map(String::from).collect(),
+            other => panic!("expected an object, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn json5_reserved_words_as_keys() {
+        let keys = json5_keys("{null: 1, true: 2, false: 3, Infinity: 4, NaN: 5}");
+        assert_eq!(keys, ["null", "true", "false", "Infinity", "NaN"]);
+        assert_eq!(
+            json5("{null: 1}").unwrap(),
+            parse_json_str(r#"{"null": 1}"#).unwrap()
+        );
+        // still values in value position
+        assert_eq!(
+            json5("{a: null}").unwrap(),
+            parse_json_str(r#"{"a": null}"#).unwrap()
+        );
+        // and not keys in strict JSON
+        assert!(parse_json_str("{null: 1}").is_err());
+
+        let options = limited(|o| o.dialect = Dialect::Json5);
+        let document = cst::Document::parse_with_options("{ null: 1 }", options).unwrap();
+        assert_eq!(document.to_string(), "{ null: 1 }");
+        assert!(document.get(&["null".into()]).is_some());
+    }
+
+    #[test]
+    fn json5_escaped_identifiers() {
+        let keys = json5_keys(r"{a\u0062: 1, \u0074rue: 2, \u00e9t\u00E9: 3}");
+        assert_eq!(keys, ["ab", "true", "été"]);
+        // an escaped keyword is only an identifier, not a value
+        assert!(json5(r"\u0074rue").is_err());
+        // the escape must stand for an identifier char
+        assert!(json5(r"{\u0031a: 1}").is_err());
+        assert!(json5(r"{a\u002d: 1}").is_err());
+        assert!(json5(r"{a\x62: 1}").is_err());
+    }
+
+    #[test]
+    fn deeply_nested_values() {
+        let options = limited(|o| o.max_depth = usize::MAX);
+        let depth = 1_000_000;
+        let arrays = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let objects = format!("{}null{}", r#"{"a":"#.repeat(depth), "}".repeat(depth));
+        for input in [arrays, objects] {
+            let value = parse_json_borrowed_with_options(&input, options.clone()).unwrap();
+            drop(value.into_owned());
+            drop(parse_json_str_with_options(&input, options.clone()).unwrap());
+        }
+    }
+
+    #[test]
+    fn depth_limit() {
+        let options = limited(|o| o.max_depth = 3);
+        assert!(parse_json_str_with_options("[[[1]]]", options.clone()).is_ok());
+        let result = parse_json_str_with_options("[[[[1]]]]", options);
+        assert!(matches!(
+            result,
+            Err(JsonError::Parse(ParseError::DepthLimitExceeded {
+                limit: 3,
+                ..
+            }))
+        ));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,468 @@
+use std::borrow::Cow;
+use std::collections::{btree_map, hash_map, BTreeMap, HashMap};
+use std::fmt;
+use std::slice;
+use std::vec;
+
+use crate::JsonValue;
+
+/// Which data structure a `Map` keeps its members in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MapBackend {
+    /// Source/insertion order, O(1) lookup. Removal is O(n) since it keeps
+    /// the remaining members in order.
+    #[default]
+    Ordered,
+    /// Sorted by key (`BTreeMap`).
+    Sorted,
+    /// Arbitrary order (`HashMap`).
+    Hashed,
+}
+
+/// The members of a JSON object. Keeps insertion order by default, see
+/// `MapBackend` for the alternatives. Keys may borrow from the parsed input,
+/// like `JsonValue::String`.
+#[derive(Clone, Default)]
+pub struct Map<'a> {
+    inner: Inner<'a>,
+}
+
+#[derive(Clone)]
+enum Inner<'a> {
+    Ordered(OrderedMap<'a>),
+    Sorted(BTreeMap<Cow<'a, str>, JsonValue<'a>>),
+    Hashed(HashMap<Cow<'a, str>, JsonValue<'a>>),
+}
+
+impl Default for Inner<'_> {
+    fn default() -> Self {
+        Inner::Ordered(OrderedMap::default())
+    }
+}
+
+/// Members in insertion order, plus an index from each key to the position of
+/// its last pair (`append` can add several pairs with the same key).
+#[derive(Clone, Default)]
+struct OrderedMap<'a> {
+    entries: Vec<(Cow<'a, str>, JsonValue<'a>)>,
+    index: HashMap<Cow<'a, str>, usize>,
+}
+
+impl OrderedMap<'_> {
+    fn has_repeated_keys(&self) -> bool {
+        self.entries.len() > self.index.len()
+    }
+}
+
+impl<'a> Map<'a> {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn with_backend(backend: MapBackend) -> Self {
+        let inner = match backend {
+            MapBackend::Ordered => Inner::Ordered(OrderedMap::default()),
+            MapBackend::Sorted => Inner::Sorted(BTreeMap::new()),
+            MapBackend::Hashed => Inner::Hashed(HashMap::new()),
+        };
+        Map { inner }
+    }
+
+    pub fn backend(&self) -> MapBackend {
+        match &self.inner {
+            Inner::Ordered(_) => MapBackend::Ordered,
+            Inner::Sorted(_) => MapBackend::Sorted,
+            Inner::Hashed(_) => MapBackend::Hashed,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.inner {
+            Inner::Ordered(m) => m.entries.len(),
+            Inner::Sorted(m) => m.len(),
+            Inner::Hashed(m) => m.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value for `key`. If `append` added several pairs with this key, it
+    /// is the value of the last one.
+    pub fn get(&self, key: &str) -> Option<&JsonValue<'a>> {
+        match &self.inner {
+            Inner::Ordered(m) => m.index.get(key).map(|&i| &m.entries[i].1),
+            Inner::Sorted(m) => m.get(key),
+            Inner::Hashed(m) => m.get(key),
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue<'a>> {
+        match &mut self.inner {
+            Inner::Ordered(m) => match m.index.get(key) {
+                Some(&i) => Some(&mut m.entries[i].1),
+                None => None,
+            },
+            Inner::Sorted(m) => m.get_mut(key),
+            Inner::Hashed(m) => m.get_mut(key),
+        }
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Every value stored for `key`, in insertion order.
+    pub fn get_all<'b>(&'b self, key: &'b str) -> impl Iterator<Item = &'b JsonValue<'a>> + 'b {
+        let (entries, single) = match &self.inner {
+            Inner::Ordered(m) if m.has_repeated_keys() => (m.entries.as_slice(), None),
+            _ => (&[][..], self.get(key)),
+        };
+        entries
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v)
+            .chain(single)
+    }
+
+    /// Inserts a member, returning the previous value for `key`. Replacing an
+    /// existing key keeps its original position.
+    pub fn insert(
+        &mut self,
+        key: impl Into<Cow<'a, str>>,
+        value: JsonValue<'a>,
+    ) -> Option<JsonValue<'a>> {
+        let key = key.into();
+        match &mut self.inner {
+            Inner::Ordered(m) => match m.index.get(&key) {
+                Some(&i) => Some(std::mem::replace(&mut m.entries[i].1, value)),
+                None => {
+                    m.index.insert(key.clone(), m.entries.len());
+                    m.entries.push((key, value));
+                    None
+                }
+            },
+            Inner::Sorted(m) => m.insert(key, value),
+            Inner::Hashed(m) => m.insert(key, value),
+        }
+    }
+
+    /// Adds a pair even if `key` is already present, keeping both. The
+    /// `Sorted` and `Hashed` backends can't hold duplicate keys, so there this
+    /// is the same as `insert`.
+    pub fn append(&mut self, key: impl Into<Cow<'a, str>>, value: JsonValue<'a>) {
+        let key = key.into();
+        match &mut self.inner {
+            Inner::Ordered(m) => {
+                m.index.insert(key.clone(), m.entries.len());
+                m.entries.push((key, value));
+            }
+            _ => {
+                self.insert(key, value);
+            }
+        }
+    }
+
+    /// Removes every pair with `key`, returning the value `get` would have
+    /// returned.
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue<'a>> {
+        match &mut self.inner {
+            Inner::Ordered(m) => {
+                let last = m.index.remove(key)?;
+                let removed: Vec<usize> = (0..=last).filter(|&i| m.entries[i].0 == key).collect();
+                let mut value = None;
+                for &i in removed.iter().rev() {
+                    let (_, v) = m.entries.remove(i);
+                    value.get_or_insert(v);
+                }
+                for position in m.index.values_mut() {
+                    *position -= removed.partition_point(|&i| i < *position);
+                }
+                value
+            }
+            Inner::Sorted(m) => m.remove(key),
+            Inner::Hashed(m) => m.remove(key),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match &mut self.inner {
+            Inner::Ordered(m) => {
+                m.entries.clear();
+                m.index.clear();
+            }
+            Inner::Sorted(m) => m.clear(),
+            Inner::Hashed(m) => m.clear(),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, 'a> {
+        let inner = match &self.inner {
+            Inner::Ordered(m) => IterInner::Ordered(m.entries.iter()),
+            Inner::Sorted(m) => IterInner::Sorted(m.iter()),
+            Inner::Hashed(m) => IterInner::Hashed(m.iter()),
+        };
+        Iter { inner }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, 'a> {
+        let inner = match &mut self.inner {
+            Inner::Ordered(m) => IterMutInner::Ordered(m.entries.iter_mut()),
+            Inner::Sorted(m) => IterMutInner::Sorted(m.iter_mut()),
+            Inner::Hashed(m) => IterMutInner::Hashed(m.iter_mut()),
+        };
+        IterMut { inner }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &JsonValue<'a>> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut JsonValue<'a>> {
+        self.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Copies any keys and strings borrowed from the input, see
+    /// `JsonValue::into_owned`.
+    pub fn into_owned(self) -> Map<'static> {
+        let mut map = Map::with_backend(self.backend());
+        for (key, value) in self {
+            map.append(key.into_owned(), value.into_owned());
+        }
+        map
+    }
+
+    fn has_repeated_keys(&self) -> bool {
+        matches!(&self.inner, Inner::Ordered(m) if m.has_repeated_keys())
+    }
+
+    /// Every value for each key, in insertion order.
+    fn groups(&self) -> HashMap<&str, Vec<&JsonValue<'_>>> {
+        let mut groups: HashMap<&str, Vec<_>> = HashMap::new();
+        for (key, value) in self.iter() {
+            groups.entry(key).or_default().push(value);
+        }
+        groups
+    }
+}
+
+/// Two maps are equal when they hold the same members, whatever their order
+/// or backend. A repeated key must repeat as often, with the same values, in
+/// both maps.
+impl PartialEq for Map<'_> {
+    fn eq(&self, other: &Map<'_>) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        if !self.has_repeated_keys() && !other.has_repeated_keys() {
+            return self.iter().all(|(k, v)| other.get(k) == Some(v));
+        }
+        let (ours, theirs) = (self.groups(), other.groups());
+        ours.len() == theirs.len()
+            && ours.iter().all(|(key, values)| match theirs.get(key) {
+                Some(others) => same_values(values, others),
+                None => false,
+            })
+    }
+}
+
+/// Whether two lists hold the same values, each as often, in any order.
+fn same_values(ours: &[&JsonValue<'_>], theirs: &[&JsonValue<'_>]) -> bool {
+    let mut theirs = theirs.to_vec();
+    for value in ours {
+        match theirs.iter().position(|other| other == value) {
+            Some(i) => theirs.swap_remove(i),
+            None => return false,
+        };
+    }
+    theirs.is_empty()
+}
+
+impl fmt::Debug for Map<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K: Into<Cow<'a, str>>> FromIterator<(K, JsonValue<'a>)> for Map<'a> {
+    fn from_iter<I: IntoIterator<Item = (K, JsonValue<'a>)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, K: Into<Cow<'a, str>>> Extend<(K, JsonValue<'a>)> for Map<'a> {
+    fn extend<I: IntoIterator<Item = (K, JsonValue<'a>)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+pub struct Iter<'b, 'a> {
+    inner: IterInner<'b, 'a>,
+}
+
+enum IterInner<'b, 'a> {
+    Ordered(slice::Iter<'b, (Cow<'a, str>, JsonValue<'a>)>),
+    Sorted(btree_map::Iter<'b, Cow<'a, str>, JsonValue<'a>>),
+    Hashed(hash_map::Iter<'b, Cow<'a, str>, JsonValue<'a>>),
+}
+
+impl<'b, 'a> Iterator for Iter<'b, 'a> {
+    type Item = (&'b str, &'b JsonValue<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = match &mut self.inner {
+            IterInner::Ordered(it) => it.next().map(|(k, v)| (k, v))?,
+            IterInner::Sorted(it) => it.next()?,
+            IterInner::Hashed(it) => it.next()?,
+        };
+        Some((k, v))
+    }
+}
+
+pub struct IterMut<'b, 'a> {
+    inner: IterMutInner<'b, 'a>,
+}
+
+enum IterMutInner<'b, 'a> {
+    Ordered(slice::IterMut<'b, (Cow<'a, str>, JsonValue<'a>)>),
+    Sorted(btree_map::IterMut<'b, Cow<'a, str>, JsonValue<'a>>),
+    Hashed(hash_map::IterMut<'b, Cow<'a, str>, JsonValue<'a>>),
+}
+
+impl<'b, 'a> Iterator for IterMut<'b, 'a> {
+    type Item = (&'b str, &'b mut JsonValue<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = match &mut self.inner {
+            IterMutInner::Ordered(it) => it.next().map(|(k, v)| (&*k, v))?,
+            IterMutInner::Sorted(it) => it.next()?,
+            IterMutInner::Hashed(it) => it.next()?,
+        };
+        Some((k, v))
+    }
+}
+
+pub struct IntoIter<'a> {
+    inner: IntoIterInner<'a>,
+}
+
+enum IntoIterInner<'a> {
+    Ordered(vec::IntoIter<(Cow<'a, str>, JsonValue<'a>)>),
+    Sorted(btree_map::IntoIter<Cow<'a, str>, JsonValue<'a>>),
+    Hashed(hash_map::IntoIter<Cow<'a, str>, JsonValue<'a>>),
+}
+
+impl<'a> Iterator for IntoIter<'a> {
+    type Item = (Cow<'a, str>, JsonValue<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            IntoIterInner::Ordered(it) => it.next(),
+            IntoIterInner::Sorted(it) => it.next(),
+            IntoIterInner::Hashed(it) => it.next(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for Map<'a> {
+    type Item = (Cow<'a, str>, JsonValue<'a>);
+    type IntoIter = IntoIter<'a>;
+
+    fn into_iter(self) -> IntoIter<'a> {
+        let inner = match self.inner {
+            Inner::Ordered(m) => IntoIterInner::Ordered(m.entries.into_iter()),
+            Inner::Sorted(m) => IntoIterInner::Sorted(m.into_iter()),
+            Inner::Hashed(m) => IntoIterInner::Hashed(m.into_iter()),
+        };
+        IntoIter { inner }
+    }
+}
+
+impl<'b, 'a> IntoIterator for &'b Map<'a> {
+    type Item = (&'b str, &'b JsonValue<'a>);
+    type IntoIter = Iter<'b, 'a>;
+
+    fn into_iter(self) -> Iter<'b, 'a> {
+        self.iter()
+    }
+}
+
+impl<'b, 'a> IntoIterator for &'b mut Map<'a> {
+    type Item = (&'b str, &'b mut JsonValue<'a>);
+    type IntoIter = IterMut<'b, 'a>;
+
+    fn into_iter(self) -> IterMut<'b, 'a> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::number::JsonNumber;
+
+    fn number(n: u64) -> JsonValue<'static> {
+        JsonValue::Number(JsonNumber::from(n))
+    }
+
+    fn pairs(backend: MapBackend, pairs: &[(&'static str, JsonValue<'static>)]) -> Map<'static> {
+        let mut map = Map::with_backend(backend);
+        for (k, v) in pairs {
+            map.append(*k, v.clone());
+        }
+        map
+    }
+
+    #[test]
+    fn equality_ignores_order_and_backend() {
+        let list = |b| JsonValue::Array(vec![JsonValue::Bool(b)]);
+        let a = pairs(MapBackend::Ordered, &[("a", number(1)), ("b", list(true))]);
+        let b = pairs(MapBackend::Hashed, &[("b", list(true)), ("a", number(1))]);
+        let c = pairs(MapBackend::Sorted, &[("a", number(1)), ("b", list(false))]);
+        assert_eq!(a, b);
+        assert_eq!(b, a);
+        assert_ne!(a, c);
+        assert_ne!(c, a);
+    }
+
+    #[test]
+    fn equality_of_large_maps() {
+        let members = (0..100_000).map(|i| (i.to_string(), number(i)));
+        let map: Map = members.collect();
+        let mut other = map.clone();
+        assert_eq!(map, other);
+        other.insert("99999", number(0));
+        assert_ne!(map, other);
+
+        // the same with a repeated key on both sides
+        let mut repeated = map.clone();
+        repeated.append("0", number(0));
+        let mut reordered: Map = (0..100_000)
+            .rev()
+            .map(|i| (i.to_string(), number(i)))
+            .collect();
+        reordered.append("0", number(0));
+        assert_eq!(repeated, reordered);
+        assert_ne!(repeated, map);
+    }
+
+    #[test]
+    fn equality_with_repeated_keys_is_symmetric() {
+        let ones = pairs(MapBackend::Ordered, &[("k", number(1)), ("k", number(1))]);
+        let mixed = pairs(MapBackend::Ordered, &[("k", number(1)), ("k", number(2))]);
+        let swapped = pairs(MapBackend::Ordered, &[("k", number(2)), ("k", number(1))]);
+        assert_ne!(ones, mixed);
+        assert_ne!(mixed, ones);
+        assert_eq!(mixed, swapped);
+        assert_eq!(swapped, mixed);
+    }
+}
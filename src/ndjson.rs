@@ -0,0 +1,214 @@
+use std::io::{self, BufRead, Read};
+
+use crate::ser::FormatOptions;
+use crate::stream::{relocate, StreamError};
+use crate::{
+    parse_bytes_with_options, JsonError, JsonValue, LimitKind, ParseError, ParserOptions, Span,
+};
+
+/// Reads newline-delimited JSON (JSON Lines), one value per line, from any
+/// `io::BufRead` such as a `BufReader<File>` or the bytes of a `&str`.
+///
+/// Each record comes with its 1-based line number. A record that fails to
+/// parse is reported as an error and reading carries on with the next line,
+/// so callers can skip bad records. Only an io error ends the iteration.
+/// Blank lines are skipped and a `\r\n` line ending is accepted. Error
+/// positions count from the start of the input, not the line.
+///
+/// `ParserOptions::max_input_bytes` limits each line. No more of a longer
+/// line than that is buffered: it is reported as `ParseError::LimitExceeded`
+/// and skipped.
+pub struct NdjsonReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    line: usize,   // number of the last line read
+    offset: usize, // bytes read so far
+    options: ParserOptions,
+    done: bool,
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    pub fn new(reader: R) -> Self {
+        NdjsonReader::with_options(reader, ParserOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        NdjsonReader {
+            reader,
+            buf: Vec::new(),
+            line: 0,
+            offset: 0,
+            options,
+            done: false,
+        }
+    }
+
+    /// Reads and throws away the rest of the current line.
+    fn skip_line(&mut self) -> io::Result<()> {
+        loop {
+            let chunk = match self.reader.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if chunk.is_empty() {
+                return Ok(());
+            }
+            let (n, found) = match chunk.iter().position(|&b| b == b'\n') {
+                Some(i) => (i + 1, true),
+                None => (chunk.len(), false),
+            };
+            self.reader.consume(n);
+            self.offset += n;
+            if found {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = (usize, Result<JsonValue<'static>, StreamError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buf.clear();
+            // read no further than the longest record and a "\r\n" after it
+            let limit = self.options.max_input_bytes;
+            let mut line = (&mut self.reader).take((limit as u64).saturating_add(2));
+            let n = match line.read_until(b'\n', &mut self.buf) {
+                Ok(0) => return None,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some((self.line + 1, Err(e.into())));
+                }
+            };
+            let cut_short = line.limit() == 0 && self.buf.last() != Some(&b'\n');
+            self.line += 1;
+            let base = Span {
+                start: self.offset,
+                end: self.offset,
+                line: self.line,
+                column: 1,
+            };
+            self.offset += n;
+
+            if cut_short {
+                if let Err(e) = self.skip_line() {
+                    self.done = true;
+                    return Some((self.line, Err(e.into())));
+                }
+                let e = ParseError::LimitExceeded {
+                    kind: LimitKind::InputSize,
+                    limit,
+                    position: base,
+                };
+                return Some((self.line, Err(JsonError::from(e).into())));
+            }
+
+            let record = self.buf.trim_ascii_end();
+            if record.is_empty() {
+                continue;
+            }
+            let value = parse_bytes_with_options(record, self.options.clone())
+                .map_err(|e| StreamError::Json(relocate(e, base)));
+            return Some((self.line, value));
+        }
+        None
+    }
+}
+
+/// Writes newline-delimited JSON: each value compact on a line of its own.
+pub struct NdjsonWriter<W: io::Write> {
+    out: W,
+    options: FormatOptions,
+}
+
+impl<W: io::Write> NdjsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        NdjsonWriter::with_options(out, FormatOptions::compact())
+    }
+
+    /// `options.indent` is ignored, as a record must stay on one line.
+    pub fn with_options(out: W, options: FormatOptions) -> Self {
+        NdjsonWriter {
+            out,
+            options: FormatOptions {
+                indent: None,
+                ..options
+            },
+        }
+    }
+
+    pub fn write_value(&mut self, value: &JsonValue<'_>) -> io::Result<()> {
+        let mut line = value.to_string_with_options(&self.options);
+        line.push('\n');
+        self.out.write_all(line.as_bytes())
+    }
+
+    /// Flushes and hands back the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json_str;
+
+    fn read(
+        input: &str,
+        options: ParserOptions,
+    ) -> Vec<(usize, Result<JsonValue<'static>, String>)> {
+        NdjsonReader::with_options(input.as_bytes(), options)
+            .map(|(line, result)| (line, result.map_err(|e| e.to_string())))
+            .collect()
+    }
+
+    fn value(input: &str) -> JsonValue<'static> {
+        parse_json_str(input).unwrap()
+    }
+
+    #[test]
+    fn reads_records_and_skips_bad_ones() {
+        let records = read("1\r\n\n[2,\n{\"a\": 3}\n", ParserOptions::default());
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], (1, Ok(value("1"))));
+        assert_eq!(records[1].0, 3);
+        // positions count from the start of the input
+        assert!(records[1].1.as_ref().unwrap_err().ends_with("at 3:4"));
+        assert_eq!(records[2], (4, Ok(value("{\"a\": 3}"))));
+    }
+
+    #[test]
+    fn long_lines_are_skipped() {
+        let options = ParserOptions {
+            max_input_bytes: 8,
+            ..ParserOptions::default()
+        };
+        let input = format!("[1]\n[{}1]\n\"123456\"\r\n2", "1,".repeat(1000));
+        let records = read(&input, options);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0], (1, Ok(value("[1]"))));
+        assert_eq!(records[1].0, 2);
+        assert_eq!(
+            records[1].1,
+            Err("input longer than 8 bytes at 2:1".to_string())
+        );
+        assert_eq!(records[2], (3, Ok(value("\"123456\""))));
+        assert_eq!(records[3], (4, Ok(value("2"))));
+    }
+
+    #[test]
+    fn writes_one_record_per_line() {
+        let mut writer = NdjsonWriter::new(Vec::new());
+        writer.write_value(&value("{\"a\": [1, 2]}")).unwrap();
+        writer.write_value(&value("\"x\"")).unwrap();
+        let out = writer.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"a\":[1,2]}\n\"x\"\n");
+    }
+}
@@ -0,0 +1,349 @@
+use std::fmt;
+
+/// A JSON number that doesn't lose precision on the way in.
+///
+/// Integers are kept as `u64` (non-negative) or `i64` (negative) when they fit,
+/// and everything else falls back to `f64`. In arbitrary-precision mode the
+/// original spelling from the input is kept instead, so any number, however
+/// long, round-trips exactly. So is the spelling of a number beyond the range
+/// of `f64`, such as `1e400`, which would otherwise become infinity.
+#[derive(Clone)]
+pub struct JsonNumber {
+    n: N,
+}
+
+#[derive(Clone)]
+enum N {
+    PosInt(u64), // always used for integers >= 0
+    NegInt(i64), // always < 0
+    Float(f64),
+    Raw(String), // original spelling, arbitrary-precision mode or too large for `f64`
+}
+
+impl JsonNumber {
+    /// Converts the text of a number token. The text must already match the
+    /// JSON or JSON5 number grammar, which the lexer guarantees. JSON5-only
+    /// spellings are converted even in arbitrary-precision mode, since they
+    /// couldn't be written back out as JSON.
+    pub(crate) fn from_token(raw: &str, arbitrary_precision: bool) -> Option<JsonNumber> {
+        if arbitrary_precision && !is_json5_only(raw) {
+            return Some(JsonNumber {
+                n: N::Raw(raw.to_string()),
+            });
+        }
+        let n = match parse_number(raw)? {
+            // valid JSON, so keep it rather than write it back as `null`
+            N::Float(f) if f.is_infinite() && !is_json5_only(raw) => N::Raw(raw.to_string()),
+            n => n,
+        };
+        Some(JsonNumber { n })
+    }
+
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    /// Whether the number is stored as a float, i.e. it has a fraction or
+    /// exponent, or is an integer too large for `i64`/`u64`.
+    pub fn is_f64(&self) -> bool {
+        match &self.n {
+            N::Float(_) => true,
+            N::Raw(raw) => matches!(parse_number(raw), Some(N::Float(_))),
+            _ => false,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match &self.n {
+            N::PosInt(u) => i64::try_from(*u).ok(),
+            N::NegInt(i) => Some(*i),
+            N::Float(_) => None,
+            N::Raw(raw) => JsonNumber {
+                n: parse_number(raw)?,
+            }
+            .as_i64(),
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match &self.n {
+            N::PosInt(u) => Some(*u),
+            N::NegInt(_) | N::Float(_) => None,
+            N::Raw(raw) => JsonNumber {
+                n: parse_number(raw)?,
+            }
+            .as_u64(),
+        }
+    }
+
+    /// The number as a float, which may round large integers and long decimals.
+    pub fn as_f64(&self) -> f64 {
+        match &self.n {
+            N::PosInt(u) => *u as f64,
+            N::NegInt(i) => *i as f64,
+            N::Float(f) => *f,
+            N::Raw(raw) => raw.parse().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// False for NaN and the infinities. Integers and numbers kept as their
+    /// spelling, which come straight from JSON text, are always finite, even
+    /// one like `1e400` that `as_f64` can only give as infinity.
+    pub fn is_finite(&self) -> bool {
+        match &self.n {
+            N::Float(f) => f.is_finite(),
+            _ => true,
+        }
+    }
+
+    /// The original spelling, if this number was parsed in arbitrary-precision
+    /// mode or is too large for `f64`.
+    pub fn as_raw(&self) -> Option<&str> {
+        match &self.n {
+            N::Raw(raw) => Some(raw),
+            _ => None,
+        }
+    }
+}
+
+fn parse_number(raw: &str) -> Option<N> {
+    let (negative, unsigned) = match raw.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, raw.strip_prefix('+').unwrap_or(raw)),
+    };
+    if let Some(hex) = unsigned
+        .strip_prefix('0')
+        .and_then(|r| r.strip_prefix(['x', 'X']))
+    {
+        return parse_hex(hex, negative);
+    }
+
+    if !raw.contains(['.', 'e', 'E']) {
+        if raw.starts_with('-') {
+            match raw.parse::<i64>() {
+                // `-0` has no integer form, keep its sign as a float
+                Ok(0) => return Some(N::Float(-0.0)),
+                Ok(i) => return Some(N::NegInt(i)),
+                Err(_) => {}
+            }
+        } else if let Ok(u) = raw.parse::<u64>() {
+            return Some(N::PosInt(u));
+        }
+    }
+    raw.parse::<f64>().ok().map(N::Float)
+}
+
+/// A JSON5 hex integer, which falls back to a float if it is too large.
+fn parse_hex(hex: &str, negative: bool) -> Option<N> {
+    let n = match u64::from_str_radix(hex, 16) {
+        Ok(n) => n,
+        Err(_) => {
+            let f = hex
+                .chars()
+                .try_fold(0.0, |f, c| Some(f * 16.0 + c.to_digit(16)? as f64))?;
+            return Some(N::Float(if negative { -f } else { f }));
+        }
+    };
+    Some(match (negative, i64::try_from(n)) {
+        (false, _) => N::PosInt(n),
+        // `-0x0` keeps its sign as a float, like `-0`
+        (true, _) if n == 0 => N::Float(-0.0),
+        (true, Ok(i)) => N::NegInt(-i),
+        (true, Err(_)) if n == 1 << 63 => N::NegInt(i64::MIN),
+        (true, Err(_)) => N::Float(-(n as f64)),
+    })
+}
+
+/// Whether `raw` is a JSON5 number with no JSON spelling: a '+' sign, hex,
+/// `Infinity`/`NaN`, or a '.' without digits on both sides.
+fn is_json5_only(raw: &str) -> bool {
+    let unsigned = raw.trim_start_matches(['+', '-']);
+    raw.starts_with('+')
+        || unsigned.starts_with(['.', 'I', 'N'])
+        || unsigned.contains(['x', 'X'])
+        || unsigned.contains(".e")
+        || unsigned.contains(".E")
+        || unsigned.ends_with('.')
+}
+
+/// Numbers are equal when they have the same value, however they are stored,
+/// so `1` parsed in arbitrary-precision mode equals `JsonNumber::from(1u64)`.
+/// Integers and spellings are compared exactly as decimals, and a float is
+/// compared with anything by its `f64` value.
+impl PartialEq for JsonNumber {
+    fn eq(&self, other: &JsonNumber) -> bool {
+        match (&self.n, &other.n) {
+            (N::PosInt(a), N::PosInt(b)) => a == b,
+            (N::NegInt(a), N::NegInt(b)) => a == b,
+            (N::PosInt(_), N::NegInt(_)) | (N::NegInt(_), N::PosInt(_)) => false,
+            (N::Float(_), _) | (_, N::Float(_)) => self.as_f64() == other.as_f64(),
+            _ => match (self.decimal(), other.decimal()) {
+                (Some(a), Some(b)) => a == b,
+                _ => self.as_f64() == other.as_f64(),
+            },
+        }
+    }
+}
+
+impl JsonNumber {
+    /// The exact value of an integer or spelling, or `None` for a float or an
+    /// exponent too large to count in `i64`.
+    fn decimal(&self) -> Option<Decimal> {
+        match &self.n {
+            N::PosInt(u) => Decimal::parse(&u.to_string()),
+            N::NegInt(i) => Decimal::parse(&i.to_string()),
+            N::Float(_) => None,
+            N::Raw(raw) => Decimal::parse(raw),
+        }
+    }
+}
+
+/// A number in the JSON grammar as `digits * 10^exponent`, with no leading or
+/// trailing zeros in `digits`, so that equal values compare equal.
+#[derive(PartialEq)]
+struct Decimal {
+    negative: bool,
+    digits: String, // empty for zero
+    exponent: i64,
+}
+
+impl Decimal {
+    fn parse(raw: &str) -> Option<Decimal> {
+        let (negative, unsigned) = match raw.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, raw),
+        };
+        let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+            None => (unsigned, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let all = format!("{}{}", int, frac);
+        let digits = all.trim_start_matches('0').trim_end_matches('0');
+        if digits.is_empty() {
+            // `-0` equals `0`
+            return Some(Decimal {
+                negative: false,
+                digits: String::new(),
+                exponent: 0,
+            });
+        }
+        let trailing_zeros = all.len() - all.trim_end_matches('0').len();
+        let exponent = exponent
+            .checked_sub(frac.len() as i64)?
+            .checked_add(trailing_zeros as i64)?;
+        Some(Decimal {
+            negative,
+            digits: digits.to_string(),
+            exponent,
+        })
+    }
+}
+
+impl From<u64> for JsonNumber {
+    fn from(u: u64) -> Self {
+        JsonNumber { n: N::PosInt(u) }
+    }
+}
+
+impl From<i64> for JsonNumber {
+    fn from(i: i64) -> Self {
+        match u64::try_from(i) {
+            Ok(u) => JsonNumber::from(u),
+            Err(_) => JsonNumber { n: N::NegInt(i) },
+        }
+    }
+}
+
+impl From<f64> for JsonNumber {
+    fn from(f: f64) -> Self {
+        JsonNumber { n: N::Float(f) }
+    }
+}
+
+impl fmt::Display for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.n {
+            N::PosInt(u) => write!(f, "{}", u),
+            N::NegInt(i) => write!(f, "{}", i),
+            // Debug keeps the `.0` and switches to exponent notation for very
+            // large or small values, where Display would print every digit
+            N::Float(x) => write!(f, "{:?}", x),
+            N::Raw(raw) => f.write_str(raw),
+        }
+    }
+}
+
+impl fmt::Debug for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonNumber;
+    use crate::{parse_json_str, parse_json_str_with_options, Dialect, JsonValue, ParserOptions};
+
+    fn number(input: &str) -> JsonNumber {
+        match &parse_json_str(input).unwrap() {
+            JsonValue::Number(n) => n.clone(),
+            other => panic!("expected a number, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn out_of_range_floats_round_trip() {
+        for input in ["1e400", "-1e400", "123.5e99999", "1E+400"] {
+            assert_eq!(number(input).to_string(), input);
+        }
+        let n = number("1e400");
+        assert_eq!(n.as_f64(), f64::INFINITY);
+        assert_eq!(n.as_i64(), None);
+        assert!(n.is_f64());
+    }
+
+    #[test]
+    fn equality_compares_values() {
+        let precise = ParserOptions {
+            arbitrary_precision: true,
+            ..ParserOptions::default()
+        };
+        let parse_precise = |input| parse_json_str_with_options(input, precise.clone()).unwrap();
+        let value = |n: JsonNumber| JsonValue::Number(n);
+
+        assert_eq!(parse_precise("1"), value(JsonNumber::from(1u64)));
+        assert_eq!(parse_precise("-7"), value(JsonNumber::from(-7i64)));
+        assert_eq!(parse_precise("[1.5]"), parse_json_str("[1.5]").unwrap());
+        assert_eq!(parse_precise("1.50"), parse_precise("15e-1"));
+        assert_eq!(parse_precise("100"), parse_precise("1E+2"));
+        assert_eq!(parse_precise("-0"), value(JsonNumber::from(0u64)));
+        assert_ne!(parse_precise("1"), value(JsonNumber::from(2u64)));
+        assert_ne!(parse_precise("1.5"), parse_precise("-1.5"));
+        assert_ne!(
+            parse_precise("123456789012345678901234567890"),
+            parse_precise("123456789012345678901234567891")
+        );
+
+        assert_eq!(number("1.0"), JsonNumber::from(1u64));
+        assert_eq!(number("1e400"), number("1e400"));
+        assert_eq!(parse_json_str("1e400").unwrap(), parse_precise("10e399"));
+        assert_eq!(number("1e400"), JsonNumber::from(f64::INFINITY));
+        assert_ne!(number("1e400"), number("1e401"));
+    }
+
+    #[test]
+    fn json5_infinity_is_still_a_float() {
+        let options = ParserOptions {
+            dialect: Dialect::Json5,
+            ..ParserOptions::default()
+        };
+        let value = parse_json_str_with_options("Infinity", options).unwrap();
+        assert!(matches!(&value, JsonValue::Number(n) if n.as_raw().is_none()));
+        assert_eq!(value.to_string(), "null");
+    }
+}
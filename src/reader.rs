@@ -0,0 +1,320 @@
+use std::borrow::Cow;
+
+use crate::{Dialect, Expected, JsonError, JsonValue, Parser, ParserOptions, Token};
+
+/// One step through a JSON document, as produced by `JsonReader`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    Key(Cow<'a, str>),
+    Value(JsonValue<'a>), // a null, bool, number or string, never an array or object
+    EndObject,
+    StartArray,
+    EndArray,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// What the reader expects to see next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Root,         // the top-level value
+    FirstKey,     // a key or '}' straight after '{'
+    Key,          // a key after ','
+    MemberValue,  // the value after "key":
+    FirstElement, // a value or ']' straight after '['
+    Element,      // a value after ','
+    AfterItem,    // ',' or the end of the open container
+    Trailing,     // nothing but end of input
+    Finished,
+}
+
+/// A pull parser that walks a document as a flat sequence of `Event`s
+/// instead of building a `JsonValue` tree, so memory use stays proportional to
+/// nesting depth however large the input is.
+///
+/// The grammar and error messages are those of `Parser`. `skip_value` passes
+/// over a subtree without building it, and `read_value` builds just the
+/// subtree at the current position. Duplicate keys are only checked inside
+/// subtrees built by `read_value`, since events never gather keys into a map.
+/// For the same reason the array, object and node count limits in
+/// `ParserOptions` only apply there; the input and string size limits apply
+/// throughout.
+pub struct JsonReader<'a> {
+    parser: Parser<'a>,
+    stack: Vec<Container>,
+    state: State,
+}
+
+impl<'a> JsonReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        JsonReader::with_options(input, ParserOptions::default())
+    }
+
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Self {
+        JsonReader {
+            parser: Parser::with_options(input, options),
+            stack: Vec::new(),
+            state: State::Root,
+        }
+    }
+
+    /// How many arrays and objects are open at the current position.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// The next event, or `None` once the whole document has been read and
+    /// nothing but whitespace follows it.
+    pub fn next_event(&mut self) -> Result<Option<Event<'a>>, JsonError> {
+        loop {
+            match self.state {
+                State::Root => {
+                    self.parser.check_input_size()?;
+                    return self.value_event().map(Some);
+                }
+                State::MemberValue => {
+                    return self.value_event().map(Some);
+                }
+                State::FirstElement | State::Element => {
+                    if self.may_end() {
+                        if let Some(Token::RBracket) = self.parser.current_token()? {
+                            return self.end_container().map(Some);
+                        }
+                    }
+                    return self.value_event().map(Some);
+                }
+                State::FirstKey | State::Key => {
+                    if self.may_end() {
+                        if let Some(Token::RBrace) = self.parser.current_token()? {
+                            return self.end_container().map(Some);
+                        }
+                    }
+                    return self.key_event().map(Some);
+                }
+                State::AfterItem => {
+                    let (next, close, expected) = match self.stack.last() {
+                        Some(Container::Object) => {
+                            (State::Key, Token::RBrace, Expected::CommaOrRBrace)
+                        }
+                        _ => (State::Element, Token::RBracket, Expected::CommaOrRBracket),
+                    };
+                    match self.parser.current_token()? {
+                        Some(Token::Comma) => {
+                            self.parser.advance(); // consume ','
+                            self.state = next;
+                        }
+                        Some(token) if *token == close => {
+                            return self.end_container().map(Some);
+                        }
+                        _ => return Err(self.parser.unexpected(expected)),
+                    }
+                }
+                State::Trailing => {
+                    if self.parser.current_token()?.is_some() {
+                        return Err(self.parser.unexpected(Expected::Eof));
+                    }
+                    self.state = State::Finished;
+                }
+                State::Finished => return Ok(None),
+            }
+        }
+    }
+
+    /// Skips the next value, however deeply nested, without building it.
+    /// Returns `false` and consumes nothing if the next event is not the start
+    /// of a value, i.e. it is a `Key`, an end, or the document is over.
+    pub fn skip_value(&mut self) -> Result<bool, JsonError> {
+        if !self.at_value()? {
+            return Ok(false);
+        }
+        let depth = self.stack.len();
+        loop {
+            self.next_event()?;
+            if self.stack.len() == depth {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Builds the next value, however deeply nested, as a `JsonValue`.
+    /// Returns `None` and consumes nothing if the next event is not the start
+    /// of a value, i.e. it is a `Key`, an end, or the document is over.
+    pub fn read_value(&mut self) -> Result<Option<JsonValue<'a>>, JsonError> {
+        if !self.at_value()? {
+            return Ok(None);
+        }
+        self.parser.nodes = 0; // `max_nodes` applies to each subtree built
+        let value = self.parser.parse_value()?;
+        self.end_item();
+        Ok(Some(value))
+    }
+
+    /// Moves past a ',' between array elements if one is next, and reports
+    /// whether a value follows.
+    fn at_value(&mut self) -> Result<bool, JsonError> {
+        match self.state {
+            State::Root => self.parser.check_input_size().map(|()| true),
+            State::MemberValue => Ok(true),
+            State::Element if !self.may_end() => Ok(true),
+            State::FirstElement | State::Element => Ok(!matches!(
+                self.parser.current_token()?,
+                Some(Token::RBracket)
+            )),
+            State::AfterItem if self.stack.last() == Some(&Container::Array) => {
+                match self.parser.current_token()? {
+                    Some(Token::Comma) => {
+                        self.parser.advance(); // consume ','
+                        self.state = State::Element;
+                        self.at_value()
+                    }
+                    Some(Token::RBracket) => Ok(false),
+                    _ => Err(self.parser.unexpected(Expected::CommaOrRBracket)),
+                }
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Whether the open container may end here: straight after it was opened,
+    /// or after a trailing comma in JSON5.
+    fn may_end(&self) -> bool {
+        matches!(self.state, State::FirstKey | State::FirstElement)
+            || self.parser.options.dialect == Dialect::Json5
+    }
+
+    fn value_event(&mut self) -> Result<Event<'a>, JsonError> {
+        let (container, state, event) = match self.parser.current_token()? {
+            Some(Token::LBrace) => (Container::Object, State::FirstKey, Event::StartObject),
+            Some(Token::LBracket) => (Container::Array, State::FirstElement, Event::StartArray),
+            _ => {
+                // anything else is a scalar, or an error `parse_value` reports
+                self.parser.nodes = 0;
+                let value = self.parser.parse_value()?;
+                self.end_item();
+                return Ok(Event::Value(value));
+            }
+        };
+        self.parser.enter()?;
+        self.parser.advance(); // consume '{' or '['
+        self.stack.push(container);
+        self.state = state;
+        Ok(event)
+    }
+
+    fn key_event(&mut self) -> Result<Event<'a>, JsonError> {
+        let expected = match self.state {
+            State::FirstKey => Expected::KeyOrRBrace,
+            _ => Expected::Key,
+        };
+        let key = match self.parser.take_key()? {
+            Some(key) => key,
+            None => return Err(self.parser.unexpected(expected)),
+        };
+        match self.parser.current_token()? {
+            Some(Token::Colon) => self.parser.advance(),
+            _ => return Err(self.parser.unexpected(Expected::Colon)),
+        }
+        self.state = State::MemberValue;
+        Ok(Event::Key(key))
+    }
+
+    /// Consumes the '}' or ']' under the cursor, which the caller has checked
+    /// matches the open container.
+    fn end_container(&mut self) -> Result<Event<'a>, JsonError> {
+        self.parser.advance();
+        self.parser.depth -= 1;
+        let event = match self.stack.pop() {
+            Some(Container::Object) => Event::EndObject,
+            _ => Event::EndArray,
+        };
+        self.end_item();
+        Ok(event)
+    }
+
+    fn end_item(&mut self) {
+        self.state = if self.stack.is_empty() {
+            State::Trailing
+        } else {
+            State::AfterItem
+        };
+    }
+}
+
+/// Yields events until the document ends or an error is found.
+impl<'a> Iterator for JsonReader<'a> {
+    type Item = Result<Event<'a>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(e) => {
+                self.state = State::Finished;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LimitKind, ParseError};
+
+    fn limit_kind(result: Result<Option<Event<'_>>, JsonError>) -> Option<LimitKind> {
+        match result {
+            Err(JsonError::Parse(ParseError::LimitExceeded { kind, .. })) => Some(kind),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn events() {
+        let mut reader = JsonReader::new(r#"{"a": [1, null], "b": {}}"#);
+        let mut events = Vec::new();
+        while let Some(event) = reader.next_event().unwrap() {
+            events.push(event);
+        }
+        assert_eq!(
+            events,
+            [
+                Event::StartObject,
+                Event::Key("a".into()),
+                Event::StartArray,
+                Event::Value(JsonValue::Number(1u64.into())),
+                Event::Value(JsonValue::Null),
+                Event::EndArray,
+                Event::Key("b".into()),
+                Event::StartObject,
+                Event::EndObject,
+                Event::EndObject,
+            ]
+        );
+    }
+
+    #[test]
+    fn size_limits_apply_to_events() {
+        let options = ParserOptions {
+            max_input_bytes: 8,
+            ..ParserOptions::default()
+        };
+        let mut reader = JsonReader::with_options("[1, 2, 3, 4]", options);
+        assert_eq!(limit_kind(reader.next_event()), Some(LimitKind::InputSize));
+
+        let options = ParserOptions {
+            max_string_length: 3,
+            ..ParserOptions::default()
+        };
+        let mut reader = JsonReader::with_options(r#"["abc", "abcd"]"#, options);
+        assert_eq!(reader.next_event().unwrap(), Some(Event::StartArray));
+        assert!(reader.next_event().is_ok());
+        assert_eq!(
+            limit_kind(reader.next_event()),
+            Some(LimitKind::StringLength)
+        );
+    }
+}
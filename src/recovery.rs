@@ -0,0 +1,497 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::mem;
+
+use crate::map::Map;
+use crate::number::JsonNumber;
+use crate::{
+    skip_string, Dialect, DuplicateKeyPolicy, Expected, JsonError, JsonValue, LimitKind,
+    ParseError, Parser, ParserOptions, Span, SpannedToken, Token,
+};
+
+/// A best-effort tree from `parse_recovering`: a JSON value that may have
+/// `Error` placeholders where the input couldn't be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartialValue<'a> {
+    Null,
+    Bool(bool),
+    Number(JsonNumber),
+    String(Cow<'a, str>),
+    Array(Vec<PartialValue<'a>>),
+    Object(Vec<(Cow<'a, str>, PartialValue<'a>)>), // in source order, repeated keys included
+    /// A value that couldn't be parsed. The span covers the input skipped
+    /// over, and is empty where the value is missing altogether.
+    Error(Span),
+}
+
+impl<'a> PartialValue<'a> {
+    /// The value as a `JsonValue`, or `None` if there is an `Error` anywhere
+    /// in it. Of repeated keys, the last one wins.
+    pub fn into_value(self) -> Option<JsonValue<'a>> {
+        let value = match self {
+            PartialValue::Null => JsonValue::Null,
+            PartialValue::Bool(b) => JsonValue::Bool(b),
+            PartialValue::Number(n) => JsonValue::Number(n),
+            PartialValue::String(s) => JsonValue::String(s),
+            PartialValue::Array(items) => JsonValue::Array(
+                items
+                    .into_iter()
+                    .map(PartialValue::into_value)
+                    .collect::<Option<_>>()?,
+            ),
+            PartialValue::Object(members) => JsonValue::Object(
+                members
+                    .into_iter()
+                    .map(|(key, value)| Some((key, value.into_value()?)))
+                    .collect::<Option<Map<'a>>>()?,
+            ),
+            PartialValue::Error(_) => return None,
+        };
+        Some(value)
+    }
+}
+
+/// What `parse_recovering` made of its input.
+#[derive(Debug)]
+pub struct Recovered<'a> {
+    pub value: PartialValue<'a>,
+    /// Every error found, in input order.
+    pub errors: Vec<JsonError>,
+}
+
+/// Parses `input` without stopping at the first error, for editors and
+/// linters that want to show every problem in a document at once.
+///
+/// A broken value becomes `PartialValue::Error`. After an unexpected token
+/// the parser skips ahead to the next ',' or closing bracket: a value that
+/// follows straight on is taken as the next item with only the ',' missing,
+/// and a bracket that closes an outer container, or the end of the input,
+/// closes every container inside it. Only the first error for what follows
+/// the top-level value is reported.
+///
+/// Arrays and objects are parsed recursively, so `max_depth` must fit the
+/// stack. The count limits of `ParserOptions` are not applied.
+pub fn parse_recovering(input: &str) -> Recovered<'_> {
+    parse_recovering_with_options(input, ParserOptions::default())
+}
+
+pub fn parse_recovering_with_options(input: &str, options: ParserOptions) -> Recovered<'_> {
+    let mut recovery = Recovery {
+        parser: Parser::with_options(input, options),
+        open: Vec::new(),
+        errors: Vec::new(),
+        eof_reported: false,
+        skipped: None,
+    };
+    let value = recovery.value();
+    if recovery.token().token != Token::Eof {
+        recovery.report(Expected::Eof);
+        while recovery.token().token != Token::Eof {
+            recovery.parser.advance();
+        }
+    }
+    Recovered {
+        value,
+        errors: recovery.errors,
+    }
+}
+
+struct Recovery<'a> {
+    parser: Parser<'a>,
+    open: Vec<Token<'static>>, // the closing bracket of each open container
+    errors: Vec<JsonError>,
+    eof_reported: bool,    // only one error is kept for the end of the input
+    skipped: Option<Span>, // the last bad token, which may stand in for a value
+}
+
+impl<'a> Recovery<'a> {
+    /// The token under the cursor, after skipping any bad ones.
+    fn token(&mut self) -> &mut SpannedToken<'a> {
+        while self.skip_bad_token().is_some() {}
+        self.parser
+            .peeked
+            .as_mut()
+            .expect("a token is peeked once there is no error")
+    }
+
+    /// If the next token can't be lexed, or is over a size limit, records the
+    /// error, moves past it and returns its span. Going over `max_input_bytes`
+    /// ends the input there.
+    fn skip_bad_token(&mut self) -> Option<Span> {
+        let e = match self.parser.peek() {
+            Ok(_) => return None,
+            Err(e) => e,
+        };
+        let span = e.span();
+        if let JsonError::Parse(ParseError::LimitExceeded {
+            kind: LimitKind::InputSize,
+            ..
+        }) = e
+        {
+            self.parser.peeked = Some(SpannedToken {
+                token: Token::Eof,
+                span: Span {
+                    end: span.start,
+                    ..span
+                },
+            });
+            self.eof_reported = true;
+        }
+        if let JsonError::Parse(ParseError::LimitExceeded {
+            kind: LimitKind::StringLength,
+            ..
+        }) = e
+        {
+            // the lexer stops partway through a string that is too long
+            let cursor = &mut self.parser.lexer.cursor;
+            skip_string(cursor, char::from(cursor.input[span.start]));
+        }
+        self.errors.push(e);
+        self.skipped = Some(span);
+        Some(span)
+    }
+
+    /// Records that the token under the cursor isn't what the grammar allows.
+    fn report(&mut self, expected: Expected) {
+        if self.token().token == Token::Eof {
+            if self.eof_reported {
+                return;
+            }
+            self.eof_reported = true;
+        }
+        let e = self.parser.unexpected(expected);
+        self.errors.push(e);
+    }
+
+    /// Whether the token under the cursor ends the current item: a ',' or
+    /// the closing bracket of an open container, or the end of the input.
+    fn at_item_end(&mut self) -> bool {
+        self.token();
+        let token = match &self.parser.peeked {
+            Some(spanned) => &spanned.token,
+            None => return true,
+        };
+        match token {
+            Token::Eof => true,
+            Token::Comma => !self.open.is_empty(),
+            Token::RBrace | Token::RBracket => self.open.contains(token),
+            _ => false,
+        }
+    }
+
+    fn value(&mut self) -> PartialValue<'a> {
+        self.token();
+        // a bad token since the last good one is taken to be the value
+        let consumed = self.parser.consumed;
+        if let Some(span) = self.skipped.take().filter(|span| span.start >= consumed) {
+            return PartialValue::Error(span);
+        }
+        let arbitrary_precision = self.parser.options.arbitrary_precision;
+        let spanned = self.token();
+        let span = spanned.span;
+        let value = match &mut spanned.token {
+            Token::LBrace | Token::LBracket => return self.container(),
+            Token::String(s) => Some(PartialValue::String(mem::take(s))),
+            Token::Number(raw) => {
+                JsonNumber::from_token(raw, arbitrary_precision).map(PartialValue::Number)
+            }
+            Token::True => Some(PartialValue::Bool(true)),
+            Token::False => Some(PartialValue::Bool(false)),
+            Token::Null => Some(PartialValue::Null),
+            _ => None,
+        };
+        if let Some(value) = value {
+            self.parser.advance();
+            return value;
+        }
+
+        self.report(Expected::Value);
+        if self.at_item_end() {
+            // the value is missing, leave the token to the container
+            return PartialValue::Error(Span {
+                end: span.start,
+                ..span
+            });
+        }
+        self.parser.advance(); // skip the stray token
+        PartialValue::Error(span)
+    }
+
+    /// Parses the array or object under the cursor. It always ends up
+    /// closed: by its own closing bracket, or where an outer container or
+    /// the input ends.
+    fn container(&mut self) -> PartialValue<'a> {
+        let start = self.token().span;
+        let object = self.token().token == Token::LBrace;
+        if let Err(e) = self.parser.enter() {
+            self.errors.push(e);
+            let end = self.skip_container();
+            return PartialValue::Error(Span { end, ..start });
+        }
+        self.parser.advance(); // consume '{' or '['
+        self.open.push(if object {
+            Token::RBrace
+        } else {
+            Token::RBracket
+        });
+        let value = if object {
+            PartialValue::Object(self.members())
+        } else {
+            PartialValue::Array(self.elements())
+        };
+        self.open.pop();
+        self.parser.depth -= 1;
+        value
+    }
+
+    fn elements(&mut self) -> Vec<PartialValue<'a>> {
+        let mut items = Vec::new();
+        if self.token().token == Token::RBracket {
+            self.parser.advance();
+            return items;
+        }
+        loop {
+            items.push(self.value());
+            if !self.next_item(Token::RBracket, Expected::CommaOrRBracket) {
+                return items;
+            }
+            if self.token().token == Token::RBracket {
+                // a trailing comma, which only JSON5 allows
+                if self.parser.options.dialect != Dialect::Json5 {
+                    self.report(Expected::Value);
+                }
+                self.parser.advance();
+                return items;
+            }
+        }
+    }
+
+    fn members(&mut self) -> Vec<(Cow<'a, str>, PartialValue<'a>)> {
+        let mut members = Vec::new();
+        if self.token().token == Token::RBrace {
+            self.parser.advance();
+            return members;
+        }
+        // where each key was first seen, only needed to report duplicates
+        let mut key_spans = HashMap::new();
+        let mut expected_key = Expected::KeyOrRBrace;
+        loop {
+            members.extend(self.member(expected_key, &mut key_spans));
+            expected_key = Expected::Key;
+            if !self.next_item(Token::RBrace, Expected::CommaOrRBrace) {
+                return members;
+            }
+            if self.token().token == Token::RBrace {
+                // a trailing comma, which only JSON5 allows
+                if self.parser.options.dialect != Dialect::Json5 {
+                    self.report(Expected::Key);
+                }
+                self.parser.advance();
+                return members;
+            }
+        }
+    }
+
+    /// Parses a `key: value` member. Without a key there is no member, and
+    /// the rest of it is skipped.
+    fn member(
+        &mut self,
+        expected_key: Expected,
+        key_spans: &mut HashMap<Cow<'a, str>, Span>,
+    ) -> Option<(Cow<'a, str>, PartialValue<'a>)> {
+        let key_span = self.token().span;
+        let key = match self.parser.take_key() {
+            Ok(Some(key)) => key,
+            _ => {
+                self.report(expected_key);
+                self.skip_item();
+                return None;
+            }
+        };
+        if self.parser.options.duplicate_keys == DuplicateKeyPolicy::Error {
+            match key_spans.get(&key) {
+                Some(&first) => self.errors.push(
+                    ParseError::DuplicateKey {
+                        key: key.clone().into_owned(),
+                        first,
+                        second: key_span,
+                    }
+                    .into(),
+                ),
+                None => {
+                    key_spans.insert(key.clone(), key_span);
+                }
+            }
+        }
+
+        if self.token().token == Token::Colon {
+            self.parser.advance();
+        } else {
+            self.report(Expected::Colon);
+            if self.at_item_end() {
+                let span = self.token().span;
+                let missing = Span {
+                    end: span.start,
+                    ..span
+                };
+                return Some((key, PartialValue::Error(missing)));
+            }
+            // carry on as if only the ':' were missing
+        }
+        Some((key, self.value()))
+    }
+
+    /// Moves past the ',' after an item and returns `true`, or past the
+    /// container's `close` bracket and returns `false`.
+    ///
+    /// Anything else is reported once and skipped, until a ',' or `close`
+    /// turns up, or the start of another item, which is taken as a missing
+    /// ','. The container also ends, without consuming anything, where an
+    /// outer one or the input does.
+    fn next_item(&mut self, close: Token<'static>, expected: Expected) -> bool {
+        let json5 = self.parser.options.dialect == Dialect::Json5;
+        let mut reported = false;
+        loop {
+            let token = &self.token().token;
+            if *token == Token::Comma {
+                self.parser.advance();
+                return true;
+            }
+            if *token == close {
+                self.parser.advance();
+                return false;
+            }
+            let starts_item = match close {
+                Token::RBrace if json5 => matches!(
+                    token,
+                    Token::String(_)
+                        | Token::Identifier(_)
+                        | Token::True
+                        | Token::False
+                        | Token::Null
+                ),
+                Token::RBrace => matches!(token, Token::String(_)),
+                _ => matches!(
+                    token,
+                    Token::LBrace
+                        | Token::LBracket
+                        | Token::String(_)
+                        | Token::Number(_)
+                        | Token::True
+                        | Token::False
+                        | Token::Null
+                ),
+            };
+            if !reported {
+                self.report(expected);
+                reported = true;
+            }
+            if starts_item {
+                return true;
+            }
+            if self.at_item_end() {
+                return false;
+            }
+            self.parser.advance();
+        }
+    }
+
+    /// Skips to the ',' or closing bracket that ends the current item,
+    /// passing over any arrays and objects in the way whole.
+    fn skip_item(&mut self) {
+        loop {
+            if matches!(self.token().token, Token::LBrace | Token::LBracket) {
+                self.skip_container();
+            } else if self.at_item_end() {
+                return;
+            } else {
+                self.parser.advance();
+            }
+        }
+    }
+
+    /// Skips the array or object under the cursor, however deeply nested,
+    /// and returns the offset just past it.
+    fn skip_container(&mut self) -> usize {
+        let mut depth = 0usize;
+        loop {
+            let spanned = self.token();
+            match spanned.token {
+                Token::LBrace | Token::LBracket => depth += 1,
+                Token::RBrace | Token::RBracket => depth -= 1,
+                Token::Eof => return spanned.span.start,
+                _ => {}
+            }
+            let end = spanned.span.end;
+            self.parser.advance();
+            if depth == 0 {
+                return end;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_a_string_over_the_limit() {
+        let options = ParserOptions {
+            max_string_length: 3,
+            ..ParserOptions::default()
+        };
+        let recovered = parse_recovering_with_options(r#"["abcdef\"gh", 1]"#, options);
+        assert_eq!(recovered.errors.len(), 1);
+        match recovered.value {
+            PartialValue::Array(items) => {
+                assert!(matches!(items[0], PartialValue::Error(_)));
+                assert_eq!(items[1], PartialValue::Number(JsonNumber::from(1u64)));
+            }
+            other => panic!("expected an array, got {:?}", other),
+        }
+    }
+
+    fn number(n: u64) -> PartialValue<'static> {
+        PartialValue::Number(JsonNumber::from(n))
+    }
+
+    #[test]
+    fn resyncs_after_errors() {
+        // a value straight after another is taken as the next item
+        let recovered = parse_recovering("[1 2, 3]");
+        assert_eq!(recovered.errors.len(), 1);
+        assert_eq!(
+            recovered.value,
+            PartialValue::Array(vec![number(1), number(2), number(3)])
+        );
+
+        // a bad member value doesn't lose the members after it
+        let recovered = parse_recovering(r#"{"a": ?, "b": 2}"#);
+        assert_eq!(recovered.errors.len(), 1);
+        match recovered.value {
+            PartialValue::Object(members) => {
+                assert!(matches!(members[0], (ref k, PartialValue::Error(_)) if k == "a"));
+                assert_eq!(members[1], ("b".into(), number(2)));
+            }
+            other => panic!("expected an object, got {:?}", other),
+        }
+
+        // the end of the input closes every open container
+        let recovered = parse_recovering("[[1, 2");
+        assert_eq!(recovered.errors.len(), 1);
+        assert_eq!(
+            recovered.value,
+            PartialValue::Array(vec![PartialValue::Array(vec![number(1), number(2)])])
+        );
+
+        // only the first error after the top-level value is reported
+        let recovered = parse_recovering("[1] 2 3");
+        assert_eq!(recovered.errors.len(), 1);
+        assert_eq!(recovered.errors[0].span().start, 4);
+        assert_eq!(
+            recovered.value.into_value(),
+            Some(JsonValue::Array(vec![JsonValue::Number(1u64.into())]))
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use std::fmt::{self, Write};
+
+use crate::number::JsonNumber;
+use crate::JsonValue;
+
+/// One level of indentation in pretty output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Spaces(usize),
+    Tabs(usize),
+}
+
+/// How to write NaN and the infinities, which JSON has no syntax for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFiniteFloats {
+    /// Write `null`, like JavaScript's `JSON.stringify`.
+    #[default]
+    Null,
+    /// Write `NaN`, `Infinity` and `-Infinity` as JSON5 does. The output is
+    /// not valid JSON.
+    Literal,
+}
+
+/// Settings for turning a `JsonValue` into text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Put each array element and object member on its own line, indented
+    /// this much per level. `None` writes everything on one line.
+    pub indent: Option<Indent>,
+    /// Written between array elements and object members.
+    pub item_separator: String,
+    /// Written between an object key and its value.
+    pub key_separator: String,
+    /// Write object members sorted by key instead of in map order.
+    pub sort_keys: bool,
+    /// Escape every non-ASCII char as `\uXXXX` (a surrogate pair above U+FFFF).
+    pub ascii_only: bool,
+    pub non_finite: NonFiniteFloats,
+}
+
+impl FormatOptions {
+    /// Everything on one line with no spaces: `{"a":[1,2]}`.
+    pub fn compact() -> Self {
+        FormatOptions {
+            indent: None,
+            item_separator: ",".to_string(),
+            key_separator: ":".to_string(),
+            sort_keys: false,
+            ascii_only: false,
+            non_finite: NonFiniteFloats::Null,
+        }
+    }
+
+    /// One element or member per line, indented by two spaces.
+    pub fn pretty() -> Self {
+        FormatOptions {
+            indent: Some(Indent::Spaces(2)),
+            key_separator: ": ".to_string(),
+            ..FormatOptions::compact()
+        }
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions::compact()
+    }
+}
+
+impl JsonValue<'_> {
+    /// The value as indented JSON, see `FormatOptions::pretty`.
+    pub fn to_string_pretty(&self) -> String {
+        self.to_string_with_options(&FormatOptions::pretty())
+    }
+
+    pub fn to_string_with_options(&self, options: &FormatOptions) -> String {
+        let mut out = String::new();
+        write_value(&mut out, self, options, 0).expect("writing to a String cannot fail");
+        out
+    }
+}
+
+/// Compact JSON, or pretty JSON with the alternate flag (`{:#}`).
+impl fmt::Display for JsonValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = if f.alternate() {
+            FormatOptions::pretty()
+        } else {
+            FormatOptions::compact()
+        };
+        write_value(f, self, &options, 0)
+    }
+}
+
+pub(crate) fn write_value<W: Write>(
+    out: &mut W,
+    value: &JsonValue<'_>,
+    options: &FormatOptions,
+    depth: usize,
+) -> fmt::Result {
+    match value {
+        JsonValue::Null => out.write_str("null"),
+        JsonValue::Bool(b) => write!(out, "{}", b),
+        JsonValue::Number(n) => write_number(out, n, options.non_finite),
+        JsonValue::String(s) => write_string(out, s, options.ascii_only),
+        JsonValue::Array(arr) => {
+            if arr.is_empty() {
+                return out.write_str("[]");
+            }
+            out.write_char('[')?;
+            for (i, item) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(&options.item_separator)?;
+                }
+                write_newline(out, options, depth + 1)?;
+                write_value(out, item, options, depth + 1)?;
+            }
+            write_newline(out, options, depth)?;
+            out.write_char(']')
+        }
+        JsonValue::Object(map) => {
+            if map.is_empty() {
+                return out.write_str("{}");
+            }
+            let mut members: Vec<_> = map.iter().collect();
+            if options.sort_keys {
+                members.sort_by_key(|(key, _)| *key);
+            }
+            out.write_char('{')?;
+            for (i, (key, item)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.write_str(&options.item_separator)?;
+                }
+                write_newline(out, options, depth + 1)?;
+                write_string(out, key, options.ascii_only)?;
+                out.write_str(&options.key_separator)?;
+                write_value(out, item, options, depth + 1)?;
+            }
+            write_newline(out, options, depth)?;
+            out.write_char('}')
+        }
+    }
+}
+
+/// Starts a new line indented for `depth`, if the output is indented at all.
+pub(crate) fn write_newline<W: Write>(
+    out: &mut W,
+    options: &FormatOptions,
+    depth: usize,
+) -> fmt::Result {
+    let (unit, width) = match options.indent {
+        Some(Indent::Spaces(width)) => (' ', width),
+        Some(Indent::Tabs(width)) => ('\t', width),
+        None => return Ok(()),
+    };
+    out.write_char('\n')?;
+    for _ in 0..width * depth {
+        out.write_char(unit)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_number<W: Write>(
+    out: &mut W,
+    n: &JsonNumber,
+    non_finite: NonFiniteFloats,
+) -> fmt::Result {
+    if n.is_finite() {
+        return write!(out, "{}", n);
+    }
+    match non_finite {
+        NonFiniteFloats::Null => out.write_str("null"),
+        NonFiniteFloats::Literal => {
+            let f = n.as_f64();
+            if f.is_nan() {
+                out.write_str("NaN")
+            } else if f > 0.0 {
+                out.write_str("Infinity")
+            } else {
+                out.write_str("-Infinity")
+            }
+        }
+    }
+}
+
+/// Writes `s` as a quoted JSON string. Quotes, backslashes and control chars
+/// are always escaped; other non-ASCII chars only with `ascii_only`.
+pub(crate) fn write_string<W: Write>(out: &mut W, s: &str, ascii_only: bool) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{8}' => out.write_str("\\b")?,
+            '\u{c}' => out.write_str("\\f")?,
+            c if c < ' ' || (ascii_only && !c.is_ascii()) => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    write!(out, "\\u{:04x}", unit)?;
+                }
+            }
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json_str;
+
+    fn format(input: &str, options: &FormatOptions) -> String {
+        parse_json_str(input)
+            .unwrap()
+            .to_string_with_options(options)
+    }
+
+    #[test]
+    fn compact_and_pretty() {
+        let value = parse_json_str(r#"{"a": [1, {}], "b": {"c": []}}"#).unwrap();
+        assert_eq!(value.to_string(), r#"{"a":[1,{}],"b":{"c":[]}}"#);
+        let pretty = "{\n  \"a\": [\n    1,\n    {}\n  ],\n  \"b\": {\n    \"c\": []\n  }\n}";
+        assert_eq!(value.to_string_pretty(), pretty);
+        assert_eq!(format!("{:#}", value), pretty);
+        assert_eq!(parse_json_str("[]").unwrap().to_string_pretty(), "[]");
+    }
+
+    #[test]
+    fn indents_and_separators() {
+        let tabs = FormatOptions {
+            indent: Some(Indent::Tabs(1)),
+            ..FormatOptions::pretty()
+        };
+        assert_eq!(
+            format(r#"{"a": [1]}"#, &tabs),
+            "{\n\t\"a\": [\n\t\t1\n\t]\n}"
+        );
+
+        let spaced = FormatOptions {
+            item_separator: ", ".to_string(),
+            key_separator: " = ".to_string(),
+            ..FormatOptions::compact()
+        };
+        assert_eq!(
+            format(r#"{"a": 1, "b": [2, 3]}"#, &spaced),
+            r#"{"a" = 1, "b" = [2, 3]}"#
+        );
+
+        let sorted = FormatOptions {
+            sort_keys: true,
+            ..FormatOptions::compact()
+        };
+        assert_eq!(
+            format(r#"{"b": 1, "a": {"z": 2, "y": 3}}"#, &sorted),
+            r#"{"a":{"y":3,"z":2},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        let value = parse_json_str(r#""q\" b\\ \n\r\t\b\f \u0000\u001f é 😀""#).unwrap();
+        assert_eq!(
+            value.to_string(),
+            r#""q\" b\\ \n\r\t\b\f \u0000\u001f é 😀""#
+        );
+        let ascii = FormatOptions {
+            ascii_only: true,
+            ..FormatOptions::compact()
+        };
+        assert_eq!(
+            value.to_string_with_options(&ascii),
+            r#""q\" b\\ \n\r\t\b\f \u0000\u001f \u00e9 \ud83d\ude00""#
+        );
+        // keys too
+        assert_eq!(format(r#"{"é": 1}"#, &ascii), r#"{"\u00e9":1}"#);
+    }
+
+    #[test]
+    fn non_finite_floats() {
+        let value = JsonValue::Array(
+            [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1.5]
+                .into_iter()
+                .map(|f| JsonValue::Number(JsonNumber::from(f)))
+                .collect(),
+        );
+        assert_eq!(value.to_string(), "[null,null,null,1.5]");
+        let literal = FormatOptions {
+            non_finite: NonFiniteFloats::Literal,
+            ..FormatOptions::compact()
+        };
+        assert_eq!(
+            value.to_string_with_options(&literal),
+            "[NaN,Infinity,-Infinity,1.5]"
+        );
+    }
+}
@@ -0,0 +1,713 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::{
+    parse_bytes_with_options, Dialect, Expected, JsonError, JsonValue, LexError, LimitKind,
+    ParseError, ParserOptions, Span, Token,
+};
+
+/// What `StreamingParser::feed` made of the input so far.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    NeedMoreData,
+    Complete(JsonValue<'static>),
+}
+
+/// Any error from parsing chunked input or an `io::Read`: `StreamingParser`,
+/// `from_reader` and `NdjsonReader`.
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Json(JsonError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "{}", e),
+            StreamError::Json(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StreamError::Io(e) => Some(e),
+            StreamError::Json(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        StreamError::Io(e)
+    }
+}
+
+impl From<JsonError> for StreamError {
+    fn from(e: JsonError) -> Self {
+        StreamError::Json(e)
+    }
+}
+
+/// How far the scanner has got through the pending value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scan {
+    /// Whitespace before the next value.
+    Between,
+    /// A number or literal, ended by a delimiter or end of input.
+    Scalar,
+    /// A top-level string, closed by `quote`.
+    String { quote: u8, escaped: bool },
+    /// An array or object, `depth` brackets deep, maybe inside a string
+    /// closed by `quote`.
+    Container {
+        depth: usize,
+        quote: Option<u8>,
+        escaped: bool,
+    },
+}
+
+/// Where the scanner is in a JSONC/JSON5 comment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comment {
+    Slash, // a '/' that may start one
+    Line,
+    Block,
+    BlockStar, // a '*' in a block comment, which may end it
+}
+
+/// Parses JSON that arrives in chunks of bytes, e.g. from a socket.
+///
+/// `feed` buffers each chunk and scans only the new bytes for the end of the
+/// current top-level value, so a string, number or multi-byte UTF-8 sequence
+/// may be split across chunks anywhere. Once a value is complete it is parsed
+/// with the normal `Parser` and returned. Bytes after it stay buffered for the
+/// next value: call `feed(&[])` to get any further values a chunk completed.
+///
+/// A top-level number or literal can only be known to be complete once a
+/// delimiter follows it, so `finish` must be called at end of input. Error
+/// positions count from the start of the stream. With `Dialect::Jsonc` or
+/// `Dialect::Json5`, brackets and quotes inside comments, and JSON5
+/// single-quoted strings, are passed over.
+///
+/// A value that grows past `ParserOptions::max_input_bytes` before it is
+/// complete fails with `ParseError::LimitExceeded` and is dropped. There is
+/// no telling where it ends, so the stream can't carry on after that: every
+/// later `feed` and `finish` fails with the same error.
+pub struct StreamingParser {
+    buffer: Vec<u8>,
+    scanned: usize, // bytes of `buffer` the scanner has looked at
+    scan: Scan,
+    comment: Option<Comment>,
+    bom_checked: bool, // whether the stream has been checked for a byte order mark
+    start: usize,      // where the pending value starts in `buffer`
+    position: Span,    // zero-width span at `scanned`, counted from the stream start
+    value_span: Span,  // the pending value, or the last one taken, so far
+    options: ParserOptions,
+    overflowed: Option<Span>, // where a value went over `max_input_bytes`
+}
+
+impl Default for StreamingParser {
+    fn default() -> Self {
+        StreamingParser::new()
+    }
+}
+
+impl StreamingParser {
+    pub fn new() -> Self {
+        StreamingParser::with_options(ParserOptions::default())
+    }
+
+    pub fn with_options(options: ParserOptions) -> Self {
+        let position = Span {
+            start: 0,
+            end: 0,
+            line: 1,
+            column: 1,
+        };
+        StreamingParser {
+            buffer: Vec::new(),
+            scanned: 0,
+            scan: Scan::Between,
+            comment: None,
+            bom_checked: false,
+            start: 0,
+            position,
+            value_span: position,
+            options,
+            overflowed: None,
+        }
+    }
+
+    /// Adds `chunk` to the input and returns the next value if it is now
+    /// complete. A value that fails to parse is dropped from the buffer, so
+    /// feeding can carry on with whatever follows it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Status, StreamError> {
+        self.check_overflow()?;
+        self.buffer.extend_from_slice(chunk);
+        match self.scan() {
+            Some(end) => self.take_value(end).map(Status::Complete),
+            None => {
+                // a '/' or part of a char may still turn out to start a value
+                let waiting = self.comment == Some(Comment::Slash)
+                    || !self.bom_checked
+                    || self.scanned < self.buffer.len();
+                if self.scan == Scan::Between && !waiting {
+                    // nothing but whitespace and comments so far, no need
+                    // to keep it
+                    self.buffer.clear();
+                    self.scanned = 0;
+                } else if self.buffer.len() - self.start > self.options.max_input_bytes {
+                    self.buffer = Vec::new();
+                    self.scanned = 0;
+                    self.overflowed = Some(self.position);
+                    self.check_overflow()?;
+                }
+                Ok(Status::NeedMoreData)
+            }
+        }
+    }
+
+    /// Ends the input and returns the value still pending, if any. Fails if
+    /// the input stopped partway through a value.
+    pub fn finish(self) -> Result<Option<JsonValue<'static>>, StreamError> {
+        Ok(self.finish_with_span()?.map(|(value, _)| value))
+    }
+
+    fn finish_with_span(mut self) -> Result<Option<(JsonValue<'static>, Span)>, StreamError> {
+        self.check_overflow()?;
+        // a partial byte order mark at the end is just bad input
+        self.bom_checked = true;
+        let end = match self.scan() {
+            Some(end) => end,
+            None if self.scan == Scan::Between => match self.comment {
+                // a '/' at the very end, let the parser report it
+                Some(Comment::Slash) => self.buffer.len(),
+                Some(Comment::Block | Comment::BlockStar) => {
+                    // `value_span` starts at the comment
+                    let span = Span {
+                        end: self.position.end,
+                        ..self.value_span
+                    };
+                    return Err(JsonError::from(LexError::UnterminatedComment(span)).into());
+                }
+                // the start of a char cut off at the end, let the parser report it
+                None if self.scanned < self.buffer.len() => self.buffer.len(),
+                _ => return Ok(None),
+            },
+            None => self.buffer.len(),
+        };
+        let value = self.take_value(end)?;
+        Ok(Some((value, self.value_span)))
+    }
+
+    /// Fails if a value has gone over `max_input_bytes`, which ends the stream.
+    fn check_overflow(&self) -> Result<(), StreamError> {
+        match self.overflowed {
+            Some(position) => Err(JsonError::from(ParseError::LimitExceeded {
+                kind: LimitKind::InputSize,
+                limit: self.options.max_input_bytes,
+                position,
+            })
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Scans the bytes not looked at yet, and returns the end of the pending
+    /// value once it has been seen.
+    fn scan(&mut self) -> Option<usize> {
+        let comments = self.options.dialect != Dialect::Strict;
+        let json5 = self.options.dialect == Dialect::Json5;
+        if !self.bom_checked {
+            let pending = &self.buffer[self.scanned..];
+            if pending.len() < BOM.len() && BOM.starts_with(pending) {
+                return None; // can't tell yet
+            }
+            self.bom_checked = true;
+            if pending.starts_with(BOM) {
+                // skip a byte order mark at the very start of the stream,
+                // without counting it as a column
+                self.scanned += BOM.len();
+                self.position.start += BOM.len();
+                self.position.end += BOM.len();
+            }
+        }
+        while let Some(&b) = self.buffer.get(self.scanned) {
+            let i = self.scanned;
+            if let Some(comment) = self.comment {
+                self.comment = match (comment, b) {
+                    (Comment::Slash, b'/') => Some(Comment::Line),
+                    (Comment::Slash, b'*') => Some(Comment::Block),
+                    // a stray '/', let the parser report it
+                    (Comment::Slash, _) => {
+                        if self.scan == Scan::Between {
+                            self.scan = Scan::Scalar; // starting at the '/'
+                        }
+                        None
+                    }
+                    (Comment::Line, b'\n') => None,
+                    (Comment::Line, _) => Some(Comment::Line),
+                    (Comment::Block | Comment::BlockStar, b'*') => Some(Comment::BlockStar),
+                    (Comment::BlockStar, b'/') => None,
+                    (Comment::Block | Comment::BlockStar, _) => Some(Comment::Block),
+                };
+                if comment != Comment::Slash || self.comment.is_some() {
+                    self.step(b);
+                    continue;
+                }
+            }
+            if json5 && matches!(self.scan, Scan::Between | Scan::Scalar) {
+                match json5_whitespace(&self.buffer[i..]) {
+                    Some(0) => {}
+                    Some(_) if self.scan == Scan::Scalar => return Some(i),
+                    Some(len) => {
+                        for _ in 0..len {
+                            self.step(self.buffer[self.scanned]);
+                        }
+                        continue;
+                    }
+                    None => {
+                        // not all of the char is here yet
+                        if self.scan == Scan::Between {
+                            self.start = i;
+                            self.value_span = self.position;
+                        }
+                        return None;
+                    }
+                }
+            }
+            let is_quote = b == b'"' || (json5 && b == b'\'');
+            match &mut self.scan {
+                Scan::Between => {
+                    if comments && b == b'/' {
+                        // a comment, or else a stray '/' the value starts at
+                        self.start = i;
+                        self.value_span = self.position;
+                        self.comment = Some(Comment::Slash);
+                    } else if !is_whitespace(b) {
+                        self.start = i;
+                        self.value_span = self.position;
+                        self.scan = match b {
+                            _ if is_quote => Scan::String {
+                                quote: b,
+                                escaped: false,
+                            },
+                            b'{' | b'[' => Scan::Container {
+                                depth: 1,
+                                quote: None,
+                                escaped: false,
+                            },
+                            // a stray delimiter, let the parser report it
+                            b'}' | b']' | b',' | b':' => {
+                                self.step(b);
+                                return Some(i + 1);
+                            }
+                            _ => Scan::Scalar,
+                        };
+                    }
+                }
+                Scan::Scalar => {
+                    if is_whitespace(b) || b"{}[],:\"".contains(&b) || (comments && b == b'/') {
+                        return Some(i);
+                    }
+                }
+                Scan::String { quote, escaped } => {
+                    if *escaped {
+                        *escaped = false;
+                    } else if b == b'\\' {
+                        *escaped = true;
+                    } else if b == *quote {
+                        self.step(b);
+                        return Some(i + 1);
+                    }
+                }
+                Scan::Container {
+                    depth,
+                    quote,
+                    escaped,
+                } => match *quote {
+                    Some(q) => {
+                        if *escaped {
+                            *escaped = false;
+                        } else if b == b'\\' {
+                            *escaped = true;
+                        } else if b == q {
+                            *quote = None;
+                        }
+                    }
+                    None => match b {
+                        _ if is_quote => *quote = Some(b),
+                        b'/' if comments => self.comment = Some(Comment::Slash),
+                        b'{' | b'[' => *depth += 1,
+                        // brackets aren't matched up here, the parser
+                        // reports a mismatch once the depth returns to 0
+                        b'}' | b']' => {
+                            *depth -= 1;
+                            if *depth == 0 {
+                                self.step(b);
+                                return Some(i + 1);
+                            }
+                        }
+                        _ => {}
+                    },
+                },
+            }
+            self.step(b);
+        }
+        None
+    }
+
+    /// Moves the scanner past `b`, keeping `position` up to date.
+    fn step(&mut self, b: u8) {
+        self.scanned += 1;
+        self.position.start += 1;
+        self.position.end += 1;
+        if b == b'\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else if b & 0xc0 != 0x80 {
+            // only the first byte of a UTF-8 sequence starts a new char
+            self.position.column += 1;
+        }
+    }
+
+    /// Parses `buffer[start..end]` and drops everything up to `end`.
+    fn take_value(&mut self, end: usize) -> Result<JsonValue<'static>, StreamError> {
+        let bytes: Vec<u8> = self.buffer.drain(..end).skip(self.start).collect();
+        self.scanned -= end;
+        self.scan = Scan::Between;
+        self.value_span.end = self.value_span.start + bytes.len();
+        let base = self.value_span;
+
+        if bytes.starts_with(BOM) && self.options.dialect != Dialect::Json5 {
+            // only the stream may start with one (JSON5 takes it as
+            // whitespace anywhere), which `parse_bytes` can't tell from the
+            // start of this value
+            let span = Span {
+                start: 0,
+                end: BOM.len(),
+                line: 1,
+                column: 1,
+            };
+            let e = JsonError::from(LexError::InvalidToken('\u{feff}', span));
+            return Err(StreamError::Json(relocate(e, base)));
+        }
+        parse_bytes_with_options(&bytes, self.options.clone())
+            .map_err(|e| StreamError::Json(relocate(e, base)))
+    }
+}
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// The length of the char at the start of `bytes` if it is whitespace in
+/// JSON5 but not in JSON, such as U+00A0 or U+2028; 0 if it isn't, and
+/// `None` if it may be but is cut off.
+fn json5_whitespace(bytes: &[u8]) -> Option<usize> {
+    let len = match bytes[0] {
+        0x0b | 0x0c => return Some(1),
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Some(0),
+    };
+    let c = std::str::from_utf8(bytes.get(..len)?)
+        .ok()
+        .and_then(|s| s.chars().next());
+    match c {
+        Some(c) if c.is_whitespace() || c == '\u{feff}' => Some(len),
+        _ => Some(0),
+    }
+}
+
+/// Moves the spans in `e`, which count from the start of one value, to count
+/// from the start of the stream, where that value begins at `base`.
+pub(crate) fn relocate(e: JsonError, base: Span) -> JsonError {
+    let at = |span: Span| Span {
+        start: base.start + span.start,
+        end: base.start + span.end,
+        line: base.line + span.line - 1,
+        column: if span.line == 1 {
+            base.column + span.column - 1
+        } else {
+            span.column
+        },
+    };
+    match e {
+        JsonError::Lex(e) => JsonError::Lex(match e {
+            LexError::InvalidToken(ch, span) => LexError::InvalidToken(ch, at(span)),
+            LexError::UnterminatedString(span) => LexError::UnterminatedString(at(span)),
+            LexError::InvalidEscape(ch, span) => LexError::InvalidEscape(ch, at(span)),
+            LexError::ControlCharacter(ch, span) => LexError::ControlCharacter(ch, at(span)),
+            LexError::InvalidUnicodeEscape(span) => LexError::InvalidUnicodeEscape(at(span)),
+            LexError::InvalidNumber(span) => LexError::InvalidNumber(at(span)),
+            LexError::UnterminatedComment(span) => LexError::UnterminatedComment(at(span)),
+            LexError::StringTooLong { limit, span } => LexError::StringTooLong {
+                limit,
+                span: at(span),
+            },
+            LexError::InvalidUtf8 { span, .. } => {
+                let span = at(span);
+                LexError::InvalidUtf8 {
+                    offset: span.start,
+                    span,
+                }
+            }
+        }),
+        JsonError::Parse(e) => JsonError::Parse(match e {
+            ParseError::UnexpectedEndOfTokens(expected, span) => {
+                ParseError::UnexpectedEndOfTokens(expected, at(span))
+            }
+            ParseError::UnexpectedToken(token, expected, span) => {
+                ParseError::UnexpectedToken(token, expected, at(span))
+            }
+            ParseError::DuplicateKey { key, first, second } => ParseError::DuplicateKey {
+                key,
+                first: at(first),
+                second: at(second),
+            },
+            ParseError::DepthLimitExceeded { limit, span } => ParseError::DepthLimitExceeded {
+                limit,
+                span: at(span),
+            },
+            ParseError::LimitExceeded {
+                kind,
+                limit,
+                position,
+            } => ParseError::LimitExceeded {
+                kind,
+                limit,
+                position: at(position),
+            },
+        }),
+    }
+}
+
+/// Reads a single JSON document from `reader` through a `StreamingParser`.
+/// Like `parse_json_str`, anything but whitespace after the value is an error.
+pub fn from_reader<R: io::Read>(reader: R) -> Result<JsonValue<'static>, StreamError> {
+    from_reader_with_options(reader, ParserOptions::default())
+}
+
+pub fn from_reader_with_options<R: io::Read>(
+    mut reader: R,
+    options: ParserOptions,
+) -> Result<JsonValue<'static>, StreamError> {
+    let mut parser = StreamingParser::with_options(options);
+    let mut chunk = [0u8; 8192];
+    let mut value = None;
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let mut status = parser.feed(&chunk[..n])?;
+        while let Status::Complete(next) = status {
+            if value.is_some() {
+                return Err(trailing_value(next, parser.value_span).into());
+            }
+            value = Some(next);
+            status = parser.feed(&[])?;
+        }
+    }
+
+    let end = parser.position;
+    match parser.finish_with_span()? {
+        Some((next, span)) => match value {
+            Some(_) => Err(trailing_value(next, span).into()),
+            None => Ok(next),
+        },
+        None => match value {
+            Some(value) => Ok(value),
+            None => {
+                Err(JsonError::from(ParseError::UnexpectedEndOfTokens(Expected::Value, end)).into())
+            }
+        },
+    }
+}
+
+/// The error `Parser` gives for a second top-level value, which is `span`.
+fn trailing_value(value: JsonValue<'static>, span: Span) -> JsonError {
+    let (token, span) = match &value {
+        JsonValue::Null => (Token::Null, span),
+        JsonValue::Bool(true) => (Token::True, span),
+        JsonValue::Bool(false) => (Token::False, span),
+        JsonValue::Number(n) => (Token::Number(n.to_string().into()), span),
+        JsonValue::String(s) => (Token::String(s.clone()), span),
+        // only the opening bracket is reported
+        JsonValue::Array(_) => (
+            Token::LBracket,
+            Span {
+                end: span.start + 1,
+                ..span
+            },
+        ),
+        JsonValue::Object(_) => (
+            Token::LBrace,
+            Span {
+                end: span.start + 1,
+                ..span
+            },
+        ),
+    };
+    ParseError::UnexpectedToken(token, Expected::Eof, span).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_input_limit(result: Result<impl fmt::Debug, StreamError>) -> bool {
+        matches!(
+            result,
+            Err(StreamError::Json(JsonError::Parse(
+                ParseError::LimitExceeded {
+                    kind: LimitKind::InputSize,
+                    ..
+                }
+            )))
+        )
+    }
+
+    fn with_dialect(dialect: Dialect) -> ParserOptions {
+        ParserOptions {
+            dialect,
+            ..ParserOptions::default()
+        }
+    }
+
+    /// Feeds `input` split at `at`, and gives back the single document it
+    /// holds, as `from_reader` would.
+    fn split_feed(
+        input: &[u8],
+        at: usize,
+        options: ParserOptions,
+    ) -> Result<JsonValue<'static>, String> {
+        let mut parser = StreamingParser::with_options(options);
+        let mut values = Vec::new();
+        for chunk in [&input[..at], &input[at..]] {
+            let mut status = parser.feed(chunk).map_err(|e| e.to_string())?;
+            while let Status::Complete(value) = status {
+                values.push(value);
+                status = parser.feed(&[]).map_err(|e| e.to_string())?;
+            }
+        }
+        values.extend(parser.finish().map_err(|e| e.to_string())?);
+        match values.len() {
+            1 => Ok(values.pop().unwrap()),
+            n => Err(format!("{} values", n)),
+        }
+    }
+
+    /// Every way of splitting `input` in two gives what `parse_bytes` gives.
+    fn check_splits(input: impl AsRef<[u8]>, options: ParserOptions) {
+        let input = input.as_ref();
+        let expected = parse_bytes_with_options(input, options.clone());
+        for at in 0..=input.len() {
+            let got = split_feed(input, at, options.clone());
+            match (&expected, &got) {
+                (Ok(expected), Ok(got)) => assert_eq!(expected, got, "{:?} split at {}", input, at),
+                (Err(expected), Err(got)) => {
+                    assert_eq!(&expected.to_string(), got, "{:?} split at {}", input, at)
+                }
+                _ => panic!("{:?} split at {}: {:?} vs {:?}", input, at, expected, got),
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_split_anywhere() {
+        for input in [
+            r#"{"a": [1, 2.5e3, -0], "b\"}": "x\\", "c": {"d": null}}"#,
+            "\u{feff}[true, false]",
+            " \"caf\u{e9} \u{1f600} \\ud83d\\ude00\" ",
+            "12345",
+            "-1.5e-3\n",
+            "[1, 2",
+            "{\"a\" 1}",
+            "\"\\x\"",
+            "\"unterminated",
+            "tru",
+        ] {
+            check_splits(input, ParserOptions::default());
+        }
+    }
+
+    #[test]
+    fn only_a_whole_bom_is_skipped() {
+        for input in [
+            &b"\xEF\xBB\xBF1 "[..],
+            b"\xEF1 ",
+            b"\xEF\xBB1 ",
+            b"\xEF",
+            b"\xEF\xBB",
+            b" \xEF\xBB\xBF1",
+        ] {
+            check_splits(input, ParserOptions::default());
+        }
+        check_splits(b" \xEF\xBB\xBF1 ", with_dialect(Dialect::Json5));
+        let mut parser = StreamingParser::new();
+        assert!(matches!(
+            parser.feed(b"\xEF1 "),
+            Err(StreamError::Json(JsonError::Lex(LexError::InvalidUtf8 {
+                offset: 0,
+                ..
+            })))
+        ));
+    }
+
+    #[test]
+    fn chunks_split_anywhere_with_comments() {
+        let jsonc = with_dialect(Dialect::Jsonc);
+        for input in [
+            "[1, \"a\" // ]\n]",
+            "/* [ */ {\"a\": /* } */ 1} // end",
+            "// only a comment\n7",
+            "1 /* trailing */",
+            "[1 /* ] */, 2]",
+            "/ 1",
+            "[1] /* unterminated",
+        ] {
+            check_splits(input, jsonc.clone());
+        }
+        let json5 = with_dialect(Dialect::Json5);
+        for input in [
+            "['a]', \"b'\", // ']\n 'c\\'d']",
+            "'x\\'y' ",
+            "{a: '}'}",
+            "\u{a0}[1, 2] ",
+            "\u{2028}1\u{2029}\u{feff}",
+            "\u{3000}'a'\u{b}\u{c}",
+            "+1\u{a0}",
+            "\u{e9}",
+            "1\u{e9}",
+        ] {
+            check_splits(input, json5.clone());
+        }
+        let mut parser = StreamingParser::with_options(json5);
+        let value = parser.feed("\u{a0}[1, 2] ".as_bytes()).unwrap();
+        assert!(matches!(value, Status::Complete(JsonValue::Array(ref a)) if a.len() == 2));
+    }
+
+    #[test]
+    fn stream_ends_after_a_value_over_the_limit() {
+        let mut parser = StreamingParser::with_options(ParserOptions {
+            max_input_bytes: 10,
+            ..ParserOptions::default()
+        });
+        assert!(is_input_limit(parser.feed(br#"["aaaaaaaaaaaaaaaa","#)));
+        // the rest of the array must not turn up as values of its own
+        assert!(is_input_limit(parser.feed(br#" "x", 5]"#)));
+        assert!(is_input_limit(parser.feed(b"1 ")));
+        assert!(is_input_limit(parser.finish()));
+    }
+}
@@ -0,0 +1,355 @@
+use std::borrow::Cow;
+use std::mem;
+use std::ops;
+
+use crate::map::Map;
+use crate::number::JsonNumber;
+use crate::JsonValue;
+
+/// What `JsonValue::get` and `value[...]` look up by: a `&str` key in an
+/// object, or a `usize` index into an array.
+pub trait JsonIndex {
+    fn index_into<'v, 'a>(&self, value: &'v JsonValue<'a>) -> Option<&'v JsonValue<'a>>;
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut JsonValue<'a>)
+        -> Option<&'v mut JsonValue<'a>>;
+}
+
+impl JsonIndex for usize {
+    fn index_into<'v, 'a>(&self, value: &'v JsonValue<'a>) -> Option<&'v JsonValue<'a>> {
+        match value {
+            JsonValue::Array(arr) => arr.get(*self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v, 'a>(
+        &self,
+        value: &'v mut JsonValue<'a>,
+    ) -> Option<&'v mut JsonValue<'a>> {
+        match value {
+            JsonValue::Array(arr) => arr.get_mut(*self),
+            _ => None,
+        }
+    }
+}
+
+impl JsonIndex for str {
+    fn index_into<'v, 'a>(&self, value: &'v JsonValue<'a>) -> Option<&'v JsonValue<'a>> {
+        match value {
+            JsonValue::Object(map) => map.get(self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v, 'a>(
+        &self,
+        value: &'v mut JsonValue<'a>,
+    ) -> Option<&'v mut JsonValue<'a>> {
+        match value {
+            JsonValue::Object(map) => map.get_mut(self),
+            _ => None,
+        }
+    }
+}
+
+impl JsonIndex for String {
+    fn index_into<'v, 'a>(&self, value: &'v JsonValue<'a>) -> Option<&'v JsonValue<'a>> {
+        self.as_str().index_into(value)
+    }
+
+    fn index_into_mut<'v, 'a>(
+        &self,
+        value: &'v mut JsonValue<'a>,
+    ) -> Option<&'v mut JsonValue<'a>> {
+        self.as_str().index_into_mut(value)
+    }
+}
+
+impl<T: JsonIndex + ?Sized> JsonIndex for &T {
+    fn index_into<'v, 'a>(&self, value: &'v JsonValue<'a>) -> Option<&'v JsonValue<'a>> {
+        (**self).index_into(value)
+    }
+
+    fn index_into_mut<'v, 'a>(
+        &self,
+        value: &'v mut JsonValue<'a>,
+    ) -> Option<&'v mut JsonValue<'a>> {
+        (**self).index_into_mut(value)
+    }
+}
+
+impl<'a> JsonValue<'a> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, JsonValue::Bool(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, JsonValue::Number(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, JsonValue::String(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, JsonValue::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, JsonValue::Object(_))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&JsonNumber> {
+        match self {
+            JsonValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// The number, if it is an integer that fits in an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number()?.as_i64()
+    }
+
+    /// The number, if it is an integer that fits in a `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number()?.as_u64()
+    }
+
+    /// Any number as a float, see `JsonNumber::as_f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        Some(self.as_number()?.as_f64())
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue<'a>>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map<'a>> {
+        match self {
+            JsonValue::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool_mut(&mut self) -> Option<&mut bool> {
+        match self {
+            JsonValue::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_number_mut(&mut self) -> Option<&mut JsonNumber> {
+        match self {
+            JsonValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// The string, copied first if it is borrowed from the input.
+    pub fn as_string_mut(&mut self) -> Option<&mut String> {
+        match self {
+            JsonValue::String(s) => Some(s.to_mut()),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue<'a>>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<'a>> {
+        match self {
+            JsonValue::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// The member for a `&str` key of an object, or the element at a `usize`
+    /// index of an array. `None` if there is none, or if this is neither.
+    pub fn get<I: JsonIndex>(&self, index: I) -> Option<&JsonValue<'a>> {
+        index.index_into(self)
+    }
+
+    pub fn get_mut<I: JsonIndex>(&mut self, index: I) -> Option<&mut JsonValue<'a>> {
+        index.index_into_mut(self)
+    }
+
+    /// Moves the value out, leaving `Null` in its place.
+    pub fn take(&mut self) -> JsonValue<'a> {
+        mem::replace(self, JsonValue::Null)
+    }
+
+    /// Looks up a JSON Pointer (RFC 6901) such as `/users/0/name`: each
+    /// `/`-separated step is an object key or an array index, with `~1`
+    /// standing for '/' and `~0` for '~' in keys. The empty pointer is the
+    /// value itself.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue<'a>> {
+        pointer_steps(pointer)?.try_fold(self, |value, step| match value {
+            JsonValue::Object(map) => map.get(&step),
+            JsonValue::Array(arr) => arr.get(array_index(&step)?),
+            _ => None,
+        })
+    }
+
+    /// Like `pointer`, but for changing the value in place, e.g. with
+    /// `take` or by assigning to it.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue<'a>> {
+        pointer_steps(pointer)?.try_fold(self, |value, step| match value {
+            JsonValue::Object(map) => map.get_mut(&step),
+            JsonValue::Array(arr) => arr.get_mut(array_index(&step)?),
+            _ => None,
+        })
+    }
+}
+
+/// The unescaped steps of a JSON Pointer, or `None` if it doesn't start
+/// with '/'.
+fn pointer_steps(pointer: &str) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+    let steps = match pointer {
+        "" => None,
+        _ => Some(pointer.strip_prefix('/')?.split('/')),
+    };
+    Some(steps.into_iter().flatten().map(|step| {
+        if step.contains('~') {
+            Cow::Owned(step.replace("~1", "/").replace("~0", "~"))
+        } else {
+            Cow::Borrowed(step)
+        }
+    }))
+}
+
+/// An array index in a JSON Pointer: decimal digits without leading zeros.
+fn array_index(step: &str) -> Option<usize> {
+    if step.is_empty() || !step.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if step.len() > 1 && step.starts_with('0') {
+        return None;
+    }
+    step.parse().ok()
+}
+
+static NULL: JsonValue<'static> = JsonValue::Null;
+
+/// `value["key"]` and `value[0]`. A missing key or index, or indexing into
+/// something that isn't an object or array, gives `Null` instead of
+/// panicking, so lookups can be chained: `value["users"][0]["name"]`.
+impl<'a, I: JsonIndex> ops::Index<I> for JsonValue<'a> {
+    type Output = JsonValue<'a>;
+
+    fn index(&self, index: I) -> &JsonValue<'a> {
+        index.index_into(self).unwrap_or(&NULL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_json_str, JsonValue};
+
+    fn sample() -> JsonValue<'static> {
+        parse_json_str(
+            r#"{"users": [{"name": "ann", "age": 30}], "a/b": 1, "m~n": 2, "~1": 3,
+                "": {"": 4, "x": [5]}, "n": null}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn get_and_index() {
+        let mut value = sample();
+        assert_eq!(
+            value.get("users").and_then(|u| u.get(0)).unwrap()["name"].as_str(),
+            Some("ann")
+        );
+        assert_eq!(value["users"][0]["age"].as_i64(), Some(30));
+        assert!(value.get("missing").is_none());
+        assert!(value["users"].get("name").is_none());
+        assert!(value["users"].get(1).is_none());
+        assert!(value.get(0).is_none());
+
+        // a missing member, and indexing into a non-container, give the shared `Null`
+        assert!(std::ptr::eq(&value["missing"], &value["n"]["x"][3]));
+        assert!(value["users"][7]["name"].is_null());
+        // unlike a `null` that is really there
+        assert!(!std::ptr::eq(&value["n"], &value["missing"]));
+
+        *value.get_mut("n").unwrap() = JsonValue::Bool(true);
+        assert_eq!(value["n"].as_bool(), Some(true));
+        assert!(value.get_mut(String::from("nope")).is_none());
+    }
+
+    #[test]
+    fn take_leaves_null() {
+        let mut value = sample();
+        let users = value.get_mut("users").unwrap().take();
+        assert!(users.is_array());
+        assert!(value["users"].is_null());
+        assert!(value.as_object().unwrap().contains_key("users"));
+    }
+
+    #[test]
+    fn pointers() {
+        let value = sample();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(
+            value.pointer("/users/0/name").unwrap().as_str(),
+            Some("ann")
+        );
+        assert_eq!(value.pointer("/a~1b").unwrap().as_i64(), Some(1));
+        assert_eq!(value.pointer("/m~0n").unwrap().as_i64(), Some(2));
+        // `~01` is an escaped '~' followed by '1', not '/'
+        assert_eq!(value.pointer("/~01").unwrap().as_i64(), Some(3));
+        // a step after '/' with nothing in it is the "" key
+        assert_eq!(value.pointer("/").unwrap()["x"][0].as_i64(), Some(5));
+        assert_eq!(value.pointer("//").unwrap().as_i64(), Some(4));
+
+        for missing in [
+            "users",
+            "/users/01",
+            "/users/-",
+            "/users/+0",
+            "/users/1",
+            "/users/0/name/x",
+            "/a/b",
+            "/missing",
+        ] {
+            assert!(value.pointer(missing).is_none(), "{}", missing);
+        }
+    }
+
+    #[test]
+    fn pointer_mut() {
+        let mut value = sample();
+        *value.pointer_mut("/users/0/age").unwrap() = JsonValue::Null;
+        assert!(value["users"][0]["age"].is_null());
+        let x = value.pointer_mut("//x").unwrap().take();
+        assert_eq!(x.as_array().map(Vec::len), Some(1));
+        assert!(value.pointer_mut("/users/00").is_none());
+        assert!(value.pointer_mut("").unwrap().is_object());
+    }
+}
@@ -0,0 +1,335 @@
+use std::error::Error;
+use std::fmt::{self, Write as _};
+use std::io::{self, Write as _};
+
+use crate::ser::{self, FormatOptions};
+use crate::JsonValue;
+
+/// Misuse of the `JsonWriter` event API, or a failure of the underlying writer.
+#[derive(Debug)]
+pub enum WriteError {
+    Io(io::Error),
+    ValueWithoutKey,  // a value inside an object where a key was expected
+    KeyOutsideObject, // `key` inside an array or at the top level
+    MissingValue,     // `key` or `end_object` straight after another `key`
+    MismatchedEnd,    // `end_object` closing an array, or `end_array` an object
+    NothingToEnd,     // `end_object`/`end_array` with no container open
+    MultipleRoots,    // a second top-level value
+    Unfinished,       // `finish` with containers still open or nothing written
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Io(e) => write!(f, "{}", e),
+            WriteError::ValueWithoutKey => {
+                write!(f, "value written inside an object without a key")
+            }
+            WriteError::KeyOutsideObject => write!(f, "key written outside an object"),
+            WriteError::MissingValue => write!(f, "key written without a value"),
+            WriteError::MismatchedEnd => write!(f, "end does not match the open container"),
+            WriteError::NothingToEnd => write!(f, "end written with no open container"),
+            WriteError::MultipleRoots => write!(f, "more than one top-level value written"),
+            WriteError::Unfinished => write!(f, "document finished before it was complete"),
+        }
+    }
+}
+
+impl Error for WriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WriteError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WriteError {
+    fn from(e: io::Error) -> Self {
+        WriteError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// An open container and how far into it the writer is.
+struct Frame {
+    container: Container,
+    len: usize,
+    has_key: bool, // an object key was written and still needs its value
+}
+
+/// Writes JSON straight to an `io::Write`, either whole `JsonValue` trees or
+/// piece by piece through events (`begin_object`, `key`, `value`,
+/// `end_object`, ...), so huge documents never have to exist in memory.
+///
+/// Output goes through a `BufWriter`, so `out` can be a `File` or
+/// `TcpStream` as it is; `finish` flushes it.
+///
+/// Events that would produce malformed JSON fail with a `WriteError` and
+/// write nothing. `FormatOptions::sort_keys` only applies to trees passed to
+/// `value`, since event-written members go out as they arrive.
+pub struct JsonWriter<W: io::Write> {
+    out: io::BufWriter<W>,
+    options: FormatOptions,
+    stack: Vec<Frame>,
+    root_done: bool,
+}
+
+impl<W: io::Write> JsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        JsonWriter::with_options(out, FormatOptions::compact())
+    }
+
+    pub fn with_options(out: W, options: FormatOptions) -> Self {
+        JsonWriter {
+            out: io::BufWriter::new(out),
+            options,
+            stack: Vec::new(),
+            root_done: false,
+        }
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriteError> {
+        self.begin_item()?;
+        self.emit(|out, _| out.write_char('{'))?;
+        self.stack.push(Frame {
+            container: Container::Object,
+            len: 0,
+            has_key: false,
+        });
+        Ok(())
+    }
+
+    pub fn end_object(&mut self) -> Result<(), WriteError> {
+        self.end(Container::Object, '}')
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriteError> {
+        self.begin_item()?;
+        self.emit(|out, _| out.write_char('['))?;
+        self.stack.push(Frame {
+            container: Container::Array,
+            len: 0,
+            has_key: false,
+        });
+        Ok(())
+    }
+
+    pub fn end_array(&mut self) -> Result<(), WriteError> {
+        self.end(Container::Array, ']')
+    }
+
+    /// Writes an object key. The next event must be its value.
+    pub fn key(&mut self, key: &str) -> Result<(), WriteError> {
+        let depth = self.stack.len();
+        let frame = match self.stack.last_mut() {
+            Some(frame) if frame.container == Container::Object => frame,
+            _ => return Err(WriteError::KeyOutsideObject),
+        };
+        if frame.has_key {
+            return Err(WriteError::MissingValue);
+        }
+        frame.has_key = true;
+        let first = frame.len == 0;
+        frame.len += 1;
+
+        self.emit(|out, options| {
+            if !first {
+                out.write_str(&options.item_separator)?;
+            }
+            ser::write_newline(out, options, depth)?;
+            ser::write_string(out, key, options.ascii_only)?;
+            out.write_str(&options.key_separator)
+        })
+    }
+
+    /// Writes a whole value, which may be a complete array or object.
+    pub fn value(&mut self, value: &JsonValue<'_>) -> Result<(), WriteError> {
+        self.begin_item()?;
+        let depth = self.stack.len();
+        self.emit(|out, options| ser::write_value(out, value, options, depth))?;
+        self.end_item();
+        Ok(())
+    }
+
+    /// Writes a string value without building a `JsonValue` for it.
+    pub fn string(&mut self, s: &str) -> Result<(), WriteError> {
+        self.begin_item()?;
+        self.emit(|out, options| ser::write_string(out, s, options.ascii_only))?;
+        self.end_item();
+        Ok(())
+    }
+
+    /// Checks that exactly one complete value was written, flushes, and hands
+    /// back the underlying writer.
+    pub fn finish(self) -> Result<W, WriteError> {
+        if !self.stack.is_empty() || !self.root_done {
+            return Err(WriteError::Unfinished);
+        }
+        self.out
+            .into_inner()
+            .map_err(|e| WriteError::Io(e.into_error()))
+    }
+
+    /// Checks that a value may go here, and writes the separator and
+    /// indentation in front of array elements.
+    fn begin_item(&mut self) -> Result<(), WriteError> {
+        let depth = self.stack.len();
+        let frame = match self.stack.last_mut() {
+            Some(frame) => frame,
+            None if self.root_done => return Err(WriteError::MultipleRoots),
+            None => return Ok(()),
+        };
+        if frame.container == Container::Object {
+            // the key already wrote everything in front of the value
+            if !frame.has_key {
+                return Err(WriteError::ValueWithoutKey);
+            }
+            frame.has_key = false;
+            return Ok(());
+        }
+        let first = frame.len == 0;
+        frame.len += 1;
+
+        self.emit(|out, options| {
+            if !first {
+                out.write_str(&options.item_separator)?;
+            }
+            ser::write_newline(out, options, depth)
+        })
+    }
+
+    fn end_item(&mut self) {
+        if self.stack.is_empty() {
+            self.root_done = true;
+        }
+    }
+
+    fn end(&mut self, container: Container, close: char) -> Result<(), WriteError> {
+        let frame = match self.stack.last() {
+            Some(frame) => frame,
+            None => return Err(WriteError::NothingToEnd),
+        };
+        if frame.container != container {
+            return Err(WriteError::MismatchedEnd);
+        }
+        if frame.has_key {
+            return Err(WriteError::MissingValue);
+        }
+        let empty = frame.len == 0;
+        self.stack.pop();
+
+        let depth = self.stack.len();
+        self.emit(|out, options| {
+            if !empty {
+                ser::write_newline(out, options, depth)?;
+            }
+            out.write_char(close)
+        })?;
+        self.end_item();
+        Ok(())
+    }
+
+    /// Runs `f` against the underlying writer through the `fmt::Write`
+    /// functions in `ser`, passing io errors back out.
+    fn emit<F>(&mut self, f: F) -> Result<(), WriteError>
+    where
+        F: FnOnce(&mut IoAdapter<'_, W>, &FormatOptions) -> fmt::Result,
+    {
+        let mut adapter = IoAdapter {
+            inner: &mut self.out,
+            error: None,
+        };
+        match f(&mut adapter, &self.options) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(WriteError::Io(
+                adapter
+                    .error
+                    .unwrap_or_else(|| io::Error::other("formatter error")),
+            )),
+        }
+    }
+}
+
+/// Lets `fmt::Write` code write to an `io::Write`, keeping the io error that
+/// `fmt::Error` can't carry.
+struct IoAdapter<'a, W: io::Write> {
+    inner: &'a mut io::BufWriter<W>,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json_str;
+
+    /// Counts the calls that would each be a syscall on a `File`.
+    #[derive(Default)]
+    struct CountingWriter {
+        bytes: Vec<u8>,
+        writes: usize,
+    }
+
+    impl io::Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes += 1;
+            self.bytes.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn buffers_small_writes() {
+        let mut writer = JsonWriter::new(CountingWriter::default());
+        writer.begin_array().unwrap();
+        for i in 0..100 {
+            let value = parse_json_str(&format!(r#"{{"i": {}, "s": "some text"}}"#, i));
+            writer.value(&value.unwrap()).unwrap();
+        }
+        writer.end_array().unwrap();
+        let out = writer.finish().unwrap();
+        assert!(out.writes <= 2, "{} writes", out.writes);
+        let text = String::from_utf8(out.bytes).unwrap();
+        assert!(text.starts_with(r#"[{"i":0,"s":"some text"},{"i":1,"#));
+    }
+
+    #[test]
+    fn rejects_malformed_events() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_object().unwrap();
+        assert!(matches!(
+            writer.value(&parse_json_str("1").unwrap()),
+            Err(WriteError::ValueWithoutKey)
+        ));
+        assert!(matches!(writer.end_array(), Err(WriteError::MismatchedEnd)));
+        writer.key("a").unwrap();
+        assert!(matches!(writer.key("b"), Err(WriteError::MissingValue)));
+        writer
+            .value(&parse_json_str(r#"[1, "x"]"#).unwrap())
+            .unwrap();
+        writer.end_object().unwrap();
+        assert!(matches!(
+            writer.begin_array(),
+            Err(WriteError::MultipleRoots)
+        ));
+        assert_eq!(writer.finish().unwrap(), br#"{"a":[1,"x"]}"#);
+    }
+}